@@ -4,6 +4,24 @@ set -euo pipefail
 echo "Building release..."
 cargo build --release
 
+# Let in-flight agents finish; the daemon exits on its own once drained.
+# A binary without `drain` prints its usage instead, so check the reply.
+DRAIN_TIMEOUT="${DRAIN_TIMEOUT:-1800}"
+drain_started() {
+    local reply
+    reply="$(~/.local/bin/agent-orchestrator drain 2>&1)" || return 1
+    [[ "$reply" == Draining* ]]
+}
+if systemctl --user is-active --quiet agent-orchestrator && drain_started; then
+    echo "Waiting up to ${DRAIN_TIMEOUT}s for agents to finish..."
+    waited=0
+    while systemctl --user is-active --quiet agent-orchestrator \
+        && [ "$waited" -lt "$DRAIN_TIMEOUT" ]; do
+        sleep 5
+        waited=$((waited + 5))
+    done
+fi
+
 echo "Stopping service..."
 systemctl --user stop agent-orchestrator || true
 sleep 1
@@ -5,6 +5,7 @@
 //! 2. Waits for messages from other agents
 //! 3. Calls llm-sdk to get a completion (with MCP tools for outbound communication)
 
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 
 use agent_bus::{Bus, Mailbox};
@@ -18,6 +19,30 @@ use crate::types::{AgentId, AgentRole};
 /// Tools blocked for non-task agents (currently unused, all agents get full tools).
 const DISALLOWED_TOOLS: &[&str] = &["Bash", "Write", "Edit", "NotebookEdit", "Agent"];
 
+/// Sent to a task agent that is mid-task when the daemon shuts down.
+const SHUTDOWN_PROMPT: &str = "The orchestrator is shutting down. Stop working on the task and \
+commit your work in progress to the current branch now \
+(git add -A && git commit -m \"WIP: <what is done so far>\"). \
+Do not report the task as complete; it will be resumed from this commit.";
+
+/// Message kind asking a task agent to commit its work and stop.
+pub const SHUTDOWN_REQUESTED: &str = "shutdown_requested";
+
+/// How an agent's run loop ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AgentExit {
+    /// Its mailbox was closed.
+    Stopped,
+    /// Stopped on `shutdown_requested`; `committed` is true when it had a
+    /// task and the commit prompt completed.
+    Shutdown { committed: bool },
+}
+
+/// Default model for the Codex backend.
+pub const DEFAULT_CODEX_MODEL: &str = "gpt-5.4";
+/// Default model for the OpenRouter backend.
+pub const DEFAULT_OPENROUTER_MODEL: &str = "anthropic/claude-sonnet-4";
+
 /// Which backend to use for completions.
 #[derive(Clone, Debug)]
 pub enum BackendKind {
@@ -26,6 +51,64 @@ pub enum BackendKind {
     Codex { model: String },
 }
 
+impl BackendKind {
+    /// Build a backend from its config name ("claude", "codex", "openrouter").
+    /// OpenRouter falls back to `OPENROUTER_API_KEY` when no key is given.
+    pub fn from_name(name: &str, model: Option<&str>, api_key: Option<String>) -> Self {
+        match name {
+            "codex" => BackendKind::Codex {
+                model: model.unwrap_or(DEFAULT_CODEX_MODEL).to_string(),
+            },
+            "openrouter" => BackendKind::OpenRouter {
+                model: model.unwrap_or(DEFAULT_OPENROUTER_MODEL).to_string(),
+                api_key: api_key
+                    .or_else(|| std::env::var("OPENROUTER_API_KEY").ok())
+                    .unwrap_or_default(),
+            },
+            _ => BackendKind::Claude,
+        }
+    }
+
+    /// The configured OpenRouter key, if this is an OpenRouter backend with one.
+    pub fn api_key(&self) -> Option<&str> {
+        match self {
+            BackendKind::OpenRouter { api_key, .. } if !api_key.is_empty() => Some(api_key),
+            _ => None,
+        }
+    }
+
+    /// Same backend with a different model (Claude has no model setting).
+    pub fn with_model(&self, model: &str) -> Self {
+        match self {
+            BackendKind::Claude => BackendKind::Claude,
+            BackendKind::OpenRouter { api_key, .. } => BackendKind::OpenRouter {
+                model: model.to_string(),
+                api_key: api_key.clone(),
+            },
+            BackendKind::Codex { .. } => BackendKind::Codex {
+                model: model.to_string(),
+            },
+        }
+    }
+
+    /// Config name of the backend, as accepted by `from_name`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackendKind::Claude => "claude",
+            BackendKind::OpenRouter { .. } => "openrouter",
+            BackendKind::Codex { .. } => "codex",
+        }
+    }
+
+    /// Configured model, if the backend has one.
+    pub fn model(&self) -> Option<&str> {
+        match self {
+            BackendKind::Claude => None,
+            BackendKind::OpenRouter { model, .. } | BackendKind::Codex { model } => Some(model),
+        }
+    }
+}
+
 /// Abstraction over Session+Claude so tests can inject a fake.
 #[async_trait]
 pub trait Completer: Send {
@@ -120,6 +203,8 @@ pub struct Agent {
     fresh_ctx: Option<FreshCtx>,
     /// Last task_assignment content received (for completion verification).
     last_task: Option<String>,
+    /// Messages that arrived during a turn, handled after it.
+    deferred: VecDeque<agent_bus::BusMessage>,
 }
 
 impl Agent {
@@ -132,6 +217,7 @@ impl Agent {
             completer,
             fresh_ctx,
             last_task: None,
+            deferred: VecDeque::new(),
         })
     }
 
@@ -147,30 +233,45 @@ impl Agent {
             completer,
             fresh_ctx: None,
             last_task: None,
+            deferred: VecDeque::new(),
         }
     }
 
     /// Run the agent main loop
-    pub async fn run(mut self) -> Result<()> {
+    pub async fn run(mut self) -> Result<AgentExit> {
         tracing::info!("Agent {} started", self.config.agent_id);
         self.process_initial_task().await;
-        while let Some(msg) = self.mailbox.recv().await {
-            self.handle_bus_message(msg).await;
-        }
+        let exit = loop {
+            let msg = match self.deferred.pop_front() {
+                Some(msg) => msg,
+                None => match self.mailbox.recv().await {
+                    Some(msg) => msg,
+                    None => break AgentExit::Stopped,
+                },
+            };
+            if msg.kind == SHUTDOWN_REQUESTED || !self.handle_bus_message(msg).await {
+                let committed = self.commit_work_in_progress().await;
+                break AgentExit::Shutdown { committed };
+            }
+        };
         tracing::info!("Agent {} stopped", self.config.agent_id);
-        Ok(())
+        Ok(exit)
     }
 
-    async fn handle_bus_message(&mut self, msg: agent_bus::BusMessage) {
+    /// Returns false when a shutdown request cut the turn short.
+    async fn handle_bus_message(&mut self, msg: agent_bus::BusMessage) -> bool {
         tracing::info!(
             "Agent {} received '{}' from {}",
             self.config.agent_id,
             msg.kind,
             msg.from
         );
-        let is_task = msg.kind == "task_assignment";
+        let is_task = is_task_message(&msg.kind);
         if is_task {
-            self.reset_completer_for_task();
+            // A resumed task continues the stored session instead of starting over.
+            if msg.kind == "task_assignment" {
+                self.reset_completer_for_task();
+            }
             self.last_task = Some(extract_content(&msg.payload));
         }
         if msg.kind == "external_message" {
@@ -183,7 +284,7 @@ impl Agent {
             msg.kind,
             content.len()
         );
-        self.dispatch_completion(&content, is_task).await;
+        self.dispatch_completion(&content, is_task).await
     }
 
     fn log_external_message(&self, payload: &serde_json::Value) {
@@ -200,8 +301,15 @@ impl Agent {
         );
     }
 
-    async fn dispatch_completion(&mut self, content: &str, is_task: bool) {
-        match self.process_prompt(content).await {
+    async fn dispatch_completion(&mut self, content: &str, is_task: bool) -> bool {
+        let Some(result) = self.process_prompt_until_shutdown(content).await else {
+            tracing::info!(
+                "Agent {} interrupted mid-turn by shutdown request",
+                self.config.agent_id
+            );
+            return false;
+        };
+        match result {
             Ok(output) if is_task => {
                 self.auto_report_completion(&output.text);
             }
@@ -220,6 +328,7 @@ impl Agent {
                 tracing::error!("Agent {} completion failed: {}", self.config.agent_id, e);
             }
         }
+        true
     }
 
     async fn process_initial_task(&mut self) {
@@ -235,6 +344,29 @@ impl Agent {
         }
     }
 
+    /// Ask the model to commit its partial work before the daemon stops.
+    /// Returns false when there was no task or the prompt failed.
+    async fn commit_work_in_progress(&mut self) -> bool {
+        if self.last_task.is_none() {
+            return false;
+        }
+        tracing::info!(
+            "Agent {} committing work in progress before shutdown",
+            self.config.agent_id
+        );
+        match self.process_prompt(SHUTDOWN_PROMPT).await {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::warn!(
+                    "Agent {} failed to commit work in progress: {}",
+                    self.config.agent_id,
+                    e
+                );
+                false
+            }
+        }
+    }
+
     fn reset_completer_for_task(&mut self) {
         let Some(ctx) = &self.fresh_ctx else {
             return;
@@ -265,6 +397,36 @@ impl Agent {
         log_completion(&self.config.agent_id, &output);
         Ok(output)
     }
+
+    /// Like `process_prompt`, but gives up on the turn when
+    /// `shutdown_requested` arrives (None), so the commit prompt can run
+    /// within the shutdown grace period. Other messages wait for the next turn.
+    async fn process_prompt_until_shutdown(
+        &mut self,
+        content: &str,
+    ) -> Option<Result<llm_sdk::Output>> {
+        let completion = self.completer.complete(content);
+        tokio::pin!(completion);
+        let mut mailbox_open = true;
+        loop {
+            tokio::select! {
+                biased;
+                result = &mut completion => {
+                    let output = match result {
+                        Ok(output) => output,
+                        Err(e) => return Some(Err(e.into())),
+                    };
+                    log_completion(&self.config.agent_id, &output);
+                    return Some(Ok(output));
+                }
+                msg = self.mailbox.recv(), if mailbox_open => match msg {
+                    Some(msg) if msg.kind == SHUTDOWN_REQUESTED => return None,
+                    Some(msg) => self.deferred.push_back(msg),
+                    None => mailbox_open = false,
+                },
+            }
+        }
+    }
 }
 
 fn build_completer(
@@ -462,6 +624,17 @@ fn log_completion(agent_id: &AgentId, output: &llm_sdk::Output) {
     }
 }
 
+/// Message kinds that carry a task: `task_assignment` starts it in a fresh
+/// session, `task_resume` continues the agent's stored session, and
+/// `check_failed` and `review_feedback` hand a failing pre-review check or a
+/// review rejection back to the agent.
+pub fn is_task_message(kind: &str) -> bool {
+    matches!(
+        kind,
+        "task_assignment" | "task_resume" | "check_failed" | "review_feedback"
+    )
+}
+
 fn format_prompt(msg: &agent_bus::BusMessage, is_task: bool) -> String {
     if is_task {
         extract_content(&msg.payload)
@@ -507,7 +680,13 @@ pub fn role_has_tools(role: AgentRole) -> bool {
 /// Build the ToolSet for an OpenRouter agent: file tools for developers, bus tools for all.
 fn build_openrouter_tools(config: &AgentConfig, bus_name: &str) -> llm_sdk::tools::ToolSet {
     let mut set = if role_has_tools(config.agent_id.role) {
-        if config.sandbox_prefix.is_empty() {
+        // Unsandboxed agents may still have an `env` prefix for the build cache.
+        if config.working_dir != llm_sdk::sandbox::REPO_MOUNT {
+            if !config.sandbox_prefix.is_empty() {
+                tracing::warn!(
+                    "{bus_name}: build cache environment is not applied to unsandboxed tools"
+                );
+            }
             llm_sdk::tools::ToolSet::standard_with_cwd(&config.working_dir)
         } else {
             llm_sdk::tools::ToolSet::standard_sandboxed(config.sandbox_prefix.clone())
@@ -1,12 +1,369 @@
 use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+use crate::agent::BackendKind;
+use crate::dispatch::{AGENT_IDLE_TIMEOUT, DispatchPolicy};
+use crate::runtime::DEFAULT_PROJECT_PRIORITY;
+
+/// Default branch task worktrees are created from and merged into.
+pub const DEFAULT_BRANCH: &str = "master";
+/// Default number of dispatches before a task is marked failed.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+/// Default number of review rejections sent back to the same agent session.
+pub const DEFAULT_MAX_REVISIONS: u32 = 2;
+/// Default wait after a task's first failed attempt (doubles per attempt).
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+/// Default time task agents get to commit their work when the daemon stops.
+pub const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(60);
+/// Default limit for one run of the pre-review check command.
+pub const DEFAULT_VERIFY_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+/// Default limit for a new worktree's post-create command.
+pub const DEFAULT_POST_CREATE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// One project entry in projects.toml. Everything except `dir` is optional
+/// and falls back to the global defaults.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
 pub struct ProjectConfig {
     pub dir: String,
+    /// Backend name: "claude", "codex" or "openrouter".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// Model for the backend (ignored by claude).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Maximum concurrent task agents for this project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_agents: Option<usize>,
+    /// Fair-share weight against other projects when slots are scarce.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u32>,
+    /// Target branch for tasks that don't name one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+    /// Run agents inside the bwrap sandbox (when available).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<bool>,
+    /// Seconds a task agent may stay idle before its task is reclaimed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<u64>,
+    /// Dispatches before a task is marked failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    /// Review rejections sent back to the agent that did the work before the
+    /// task is requeued for a fresh attempt (0 always requeues).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_revisions: Option<u32>,
+    /// Seconds to wait after a task's first failed attempt; doubles per attempt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_backoff: Option<u64>,
+    /// Seconds task agents get to commit work in progress on shutdown (0 aborts at once).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shutdown_grace: Option<u64>,
+    /// Ready-task ordering: "priority" (default) or "fifo".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dispatch_policy: Option<DispatchPolicy>,
+    /// Build/test command a native merge must pass, in a scratch worktree,
+    /// before the target branch is moved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_command: Option<String>,
+    /// Check run in the task worktree when an agent reports completion,
+    /// before review (e.g. `cargo test`). Unset skips the stage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_command: Option<String>,
+    /// Seconds one run of `verify_command` may take.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_timeout: Option<u64>,
+    /// Extra writable sandbox mounts, as `host` or `host:sandbox_path`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_mounts: Vec<String>,
+    /// How task worktrees are prepared (`[<project>.worktree]`).
+    #[serde(default, skip_serializing_if = "WorktreeSetup::is_default")]
+    pub worktree: WorktreeSetup,
+    /// Who validates new tasks and reviews completed ones (`[<project>.reviewer]`).
+    #[serde(default, skip_serializing_if = "ReviewerConfig::is_default")]
+    pub reviewer: ReviewerConfig,
+}
+
+/// Reviewer for task validation and completion review.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReviewerConfig {
+    pub backend: ReviewerBackend,
+    /// Model for the backend; each backend has its own default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Project-specific instructions added to every review prompt.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// OpenRouter key; defaults to the project's, then the global backend's.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Leave tasks pending or in_review when the reviewer fails, instead of
+    /// approving them unreviewed.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub strict: bool,
+}
+
+impl ReviewerConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Reviewer backend (`backend` in `[reviewer]`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewerBackend {
+    /// Validation by the claude-architect daemon; completion review by the
+    /// claude CLI, reported back to the daemon.
+    #[default]
+    Architect,
+    /// The claude CLI for both.
+    Claude,
+    OpenRouter,
+    Codex,
+}
+
+/// Per-project preparation of task worktrees. Paths are relative to the
+/// project root.
+///
+/// The `.worktrees/<alias>` symlink and `/tmp/<alias>` sandbox mount that
+/// `*-phpstan-fixes` projects used to get implicitly are configured here now;
+/// for `gc-phpstan-fixes`:
+///
+/// ```toml
+/// [gc-phpstan-fixes.worktree]
+/// aliases = ["gc"]
+/// mounts = ["{worktree}:/tmp/gc"]
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WorktreeSetup {
+    /// Directories symlinked into every worktree, shared with the project root.
+    pub link: Vec<String>,
+    /// Files or directories copied into a new worktree if missing (e.g. `.env`).
+    pub copy: Vec<String>,
+    /// Shell command run in a newly created worktree (e.g. `composer install`),
+    /// inside the agent's sandbox before it starts on the task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_create: Option<String>,
+    /// Seconds `post_create` may run before it is stopped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_create_timeout: Option<u64>,
+    /// Names of `.worktrees/<name>` symlinks pointing at the latest worktree.
+    pub aliases: Vec<String>,
+    /// Extra sandbox mounts like `extra_mounts`; `{worktree}` expands to the
+    /// agent's worktree.
+    pub mounts: Vec<String>,
+    /// How Rust builds in task worktrees share compiled artifacts.
+    #[serde(skip_serializing_if = "BuildCache::is_none")]
+    pub build_cache: BuildCache,
+}
+
+/// Build-cache strategy for task worktrees (`build_cache` in `[worktree]`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BuildCache {
+    /// Every worktree builds into its own `target/`.
+    #[default]
+    None,
+    /// `CARGO_TARGET_DIR` pointing at one of a pool of per-slot target dirs,
+    /// so concurrent agents don't block on each other's cargo lock.
+    SharedTarget,
+    /// `RUSTC_WRAPPER=sccache` with a cache dir shared by all agents.
+    Sccache,
+    /// Copy-on-write clone of the project's `target/` into new worktrees.
+    Reflink,
+}
+
+impl BuildCache {
+    fn is_none(&self) -> bool {
+        *self == Self::None
+    }
+
+    /// Strategies that reach the agent's builds through environment variables.
+    fn needs_env(&self) -> bool {
+        matches!(self, Self::SharedTarget | Self::Sccache)
+    }
+}
+
+impl Default for WorktreeSetup {
+    fn default() -> Self {
+        Self {
+            link: crate::worktree::SHARED_DEPENDENCY_DIRS
+                .iter()
+                .map(|dir| dir.to_string())
+                .collect(),
+            copy: Vec::new(),
+            post_create: None,
+            post_create_timeout: None,
+            aliases: Vec::new(),
+            mounts: Vec::new(),
+            build_cache: BuildCache::None,
+        }
+    }
+}
+
+impl WorktreeSetup {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn post_create_timeout(&self) -> Duration {
+        self.post_create_timeout
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_POST_CREATE_TIMEOUT)
+    }
+}
+
+/// Effective runtime settings for one project: projects.toml entry over global defaults.
+#[derive(Clone, Debug)]
+pub struct ProjectSettings {
+    pub backend: BackendKind,
+    pub max_agents: Option<usize>,
+    pub priority: u32,
+    pub default_branch: String,
+    pub sandbox: bool,
+    pub idle_timeout: Duration,
+    pub max_attempts: u32,
+    pub max_revisions: u32,
+    pub retry_backoff: Duration,
+    pub shutdown_grace: Duration,
+    pub dispatch_policy: DispatchPolicy,
+    pub test_command: Option<String>,
+    pub verify_command: Option<String>,
+    pub verify_timeout: Duration,
+    /// Writable sandbox mounts as (host path, sandbox path), `extra_mounts`
+    /// followed by the worktree setup's mounts.
+    pub extra_mounts: Vec<(String, String)>,
+    pub worktree: WorktreeSetup,
+    pub reviewer: ReviewerConfig,
+}
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            backend: BackendKind::Claude,
+            max_agents: None,
+            priority: DEFAULT_PROJECT_PRIORITY,
+            default_branch: DEFAULT_BRANCH.to_string(),
+            sandbox: true,
+            idle_timeout: AGENT_IDLE_TIMEOUT,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            max_revisions: DEFAULT_MAX_REVISIONS,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
+            dispatch_policy: DispatchPolicy::default(),
+            test_command: None,
+            verify_command: None,
+            verify_timeout: DEFAULT_VERIFY_TIMEOUT,
+            extra_mounts: Vec::new(),
+            worktree: WorktreeSetup::default(),
+            reviewer: ReviewerConfig::default(),
+        }
+    }
+}
+
+impl ProjectConfig {
+    /// Resolve this entry against the daemon-wide backend and sandbox flag.
+    pub fn settings(&self, default_backend: &BackendKind, no_sandbox: bool) -> ProjectSettings {
+        let defaults = ProjectSettings::default();
+        let backend = self.resolve_backend(default_backend);
+        let reviewer = ReviewerConfig {
+            api_key: self
+                .reviewer
+                .api_key
+                .as_deref()
+                .or(backend.api_key())
+                .or(default_backend.api_key())
+                .map(str::to_string),
+            ..self.reviewer.clone()
+        };
+        let sandbox = !no_sandbox && self.sandbox.unwrap_or(defaults.sandbox);
+        let mut worktree = self.worktree.clone();
+        // Unsandboxed OpenRouter and Codex tools run without the agent's env
+        // prefix, so they would never see the cache variables.
+        if !sandbox && worktree.build_cache.needs_env() && !matches!(backend, BackendKind::Claude) {
+            tracing::warn!(
+                "Ignoring build_cache for {}: it needs the sandbox with the {} backend",
+                self.dir,
+                backend.name()
+            );
+            worktree.build_cache = BuildCache::None;
+        }
+        ProjectSettings {
+            backend,
+            max_agents: self.max_agents,
+            priority: self.priority.unwrap_or(defaults.priority),
+            default_branch: self
+                .default_branch
+                .clone()
+                .unwrap_or(defaults.default_branch),
+            sandbox,
+            idle_timeout: self
+                .idle_timeout
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.idle_timeout),
+            max_attempts: self.max_attempts.unwrap_or(defaults.max_attempts),
+            max_revisions: self.max_revisions.unwrap_or(defaults.max_revisions),
+            retry_backoff: self
+                .retry_backoff
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.retry_backoff),
+            shutdown_grace: self
+                .shutdown_grace
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.shutdown_grace),
+            dispatch_policy: self.dispatch_policy.unwrap_or(defaults.dispatch_policy),
+            test_command: self.test_command.clone(),
+            verify_command: self.verify_command.clone(),
+            verify_timeout: self
+                .verify_timeout
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.verify_timeout),
+            extra_mounts: self
+                .extra_mounts
+                .iter()
+                .chain(&self.worktree.mounts)
+                .map(|m| parse_mount(m))
+                .collect(),
+            worktree,
+            reviewer,
+        }
+    }
+
+    fn resolve_backend(&self, default_backend: &BackendKind) -> BackendKind {
+        match (&self.backend, &self.model) {
+            (Some(name), model) => BackendKind::from_name(
+                name,
+                model.as_deref(),
+                default_backend.api_key().map(str::to_string),
+            ),
+            (None, Some(model)) => default_backend.with_model(model),
+            (None, None) => default_backend.clone(),
+        }
+    }
+}
+
+/// Parse `host` or `host:sandbox_path`, expanding a leading `~/` on the host side.
+fn parse_mount(spec: &str) -> (String, String) {
+    let (host, mount) = spec.split_once(':').unwrap_or((spec, spec));
+    let host = match host.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest).to_string_lossy().into_owned())
+            .unwrap_or_else(|| host.to_string()),
+        None => host.to_string(),
+    };
+    let mount = if mount == spec {
+        host.clone()
+    } else {
+        mount.to_string()
+    };
+    (host, mount)
 }
 
 pub fn config_path() -> PathBuf {
@@ -36,12 +393,7 @@ pub fn ensure_project_registered(project: &str, dir: &str) -> Result<bool> {
         return Ok(false);
     }
 
-    projects.insert(
-        project.to_string(),
-        ProjectConfig {
-            dir: dir.to_string(),
-        },
-    );
+    projects.entry(project.to_string()).or_default().dir = dir.to_string();
     write_config(&path, &projects)?;
     Ok(true)
 }
@@ -99,6 +451,182 @@ mod tests {
         result
     }
 
+    #[test]
+    fn project_settings_fall_back_to_global_defaults() {
+        let cfg: ProjectConfig = toml::from_str("dir = \"/repo/small\"").expect("parse");
+        let settings = cfg.settings(&BackendKind::Claude, false);
+
+        assert!(matches!(settings.backend, BackendKind::Claude));
+        assert_eq!(settings.default_branch, DEFAULT_BRANCH);
+        assert_eq!(settings.max_attempts, DEFAULT_MAX_ATTEMPTS);
+        assert_eq!(settings.max_revisions, DEFAULT_MAX_REVISIONS);
+        assert_eq!(settings.dispatch_policy, DispatchPolicy::Priority);
+        assert_eq!(settings.idle_timeout, AGENT_IDLE_TIMEOUT);
+        assert_eq!(settings.shutdown_grace, DEFAULT_SHUTDOWN_GRACE);
+        assert!(settings.verify_command.is_none());
+        assert!(settings.sandbox);
+        assert!(settings.max_agents.is_none());
+        assert_eq!(settings.worktree.link, vec!["vendor", "node_modules"]);
+        assert_eq!(settings.reviewer.backend, ReviewerBackend::Architect);
+        assert!(!settings.reviewer.strict);
+    }
+
+    #[test]
+    fn project_settings_apply_per_project_overrides() {
+        let cfg: ProjectConfig = toml::from_str(
+            r#"
+            dir = "/repo/monorepo"
+            backend = "codex"
+            model = "gpt-5.4-mini"
+            max_agents = 2
+            priority = 3
+            default_branch = "main"
+            sandbox = false
+            idle_timeout = 3600
+            max_attempts = 5
+            max_revisions = 0
+            retry_backoff = 300
+            shutdown_grace = 120
+            dispatch_policy = "fifo"
+            test_command = "composer test"
+            verify_command = "vendor/bin/phpstan"
+            verify_timeout = 600
+            extra_mounts = ["/var/cache/composer", "/srv/fixtures:/fixtures"]
+
+            [worktree]
+            link = ["vendor"]
+            copy = [".env"]
+            post_create = "composer install"
+            post_create_timeout = 900
+            aliases = ["gc"]
+            mounts = ["{worktree}:/tmp/gc"]
+            build_cache = "reflink"
+
+            [reviewer]
+            backend = "openrouter"
+            model = "openai/gpt-5-mini"
+            prompt = "Reject changes without tests."
+            strict = true
+            "#,
+        )
+        .expect("parse");
+        let settings = cfg.settings(&BackendKind::Claude, false);
+
+        assert!(
+            matches!(settings.backend, BackendKind::Codex { ref model } if model == "gpt-5.4-mini")
+        );
+        assert_eq!(settings.max_agents, Some(2));
+        assert_eq!(settings.priority, 3);
+        assert_eq!(settings.default_branch, "main");
+        assert!(!settings.sandbox);
+        assert_eq!(settings.idle_timeout, Duration::from_secs(3600));
+        assert_eq!(settings.max_attempts, 5);
+        assert_eq!(settings.max_revisions, 0);
+        assert_eq!(settings.retry_backoff, Duration::from_secs(300));
+        assert_eq!(settings.shutdown_grace, Duration::from_secs(120));
+        assert_eq!(settings.dispatch_policy, DispatchPolicy::Fifo);
+        assert_eq!(settings.test_command.as_deref(), Some("composer test"));
+        assert_eq!(
+            settings.verify_command.as_deref(),
+            Some("vendor/bin/phpstan")
+        );
+        assert_eq!(settings.verify_timeout, Duration::from_secs(600));
+        assert_eq!(
+            settings.extra_mounts,
+            vec![
+                (
+                    "/var/cache/composer".to_string(),
+                    "/var/cache/composer".to_string()
+                ),
+                ("/srv/fixtures".to_string(), "/fixtures".to_string()),
+                ("{worktree}".to_string(), "/tmp/gc".to_string()),
+            ]
+        );
+        assert_eq!(settings.worktree.link, vec!["vendor"]);
+        assert_eq!(settings.worktree.copy, vec![".env"]);
+        assert_eq!(
+            settings.worktree.post_create.as_deref(),
+            Some("composer install")
+        );
+        assert_eq!(
+            settings.worktree.post_create_timeout(),
+            Duration::from_secs(900)
+        );
+        assert_eq!(settings.worktree.aliases, vec!["gc"]);
+        assert_eq!(settings.worktree.build_cache, BuildCache::Reflink);
+        assert_eq!(settings.reviewer.backend, ReviewerBackend::OpenRouter);
+        assert_eq!(
+            settings.reviewer.model.as_deref(),
+            Some("openai/gpt-5-mini")
+        );
+        assert!(settings.reviewer.strict);
+    }
+
+    #[test]
+    fn env_build_cache_needs_the_sandbox_outside_claude() {
+        let mut cfg = ProjectConfig {
+            dir: "/repo".to_string(),
+            backend: Some("codex".to_string()),
+            sandbox: Some(false),
+            ..Default::default()
+        };
+        cfg.worktree.build_cache = BuildCache::SharedTarget;
+
+        let settings = cfg.settings(&BackendKind::Claude, false);
+        assert_eq!(settings.worktree.build_cache, BuildCache::None);
+
+        cfg.sandbox = Some(true);
+        let settings = cfg.settings(&BackendKind::Claude, false);
+        assert_eq!(settings.worktree.build_cache, BuildCache::SharedTarget);
+
+        cfg.backend = Some("claude".to_string());
+        let settings = cfg.settings(&BackendKind::Claude, true);
+        assert_eq!(settings.worktree.build_cache, BuildCache::SharedTarget);
+    }
+
+    #[test]
+    fn model_override_keeps_global_backend_kind() {
+        let cfg = ProjectConfig {
+            dir: "/repo".to_string(),
+            model: Some("openai/gpt-5".to_string()),
+            ..Default::default()
+        };
+        let global = BackendKind::OpenRouter {
+            model: "anthropic/claude-sonnet-4".to_string(),
+            api_key: "key".to_string(),
+        };
+
+        let settings = cfg.settings(&global, true);
+
+        assert!(matches!(
+            settings.backend,
+            BackendKind::OpenRouter { ref model, ref api_key } if model == "openai/gpt-5" && api_key == "key"
+        ));
+        assert!(!settings.sandbox, "--no-sandbox wins over project config");
+    }
+
+    #[test]
+    fn openrouter_reviewer_inherits_the_global_key() {
+        let cfg = ProjectConfig {
+            dir: "/repo".to_string(),
+            backend: Some("claude".to_string()),
+            reviewer: ReviewerConfig {
+                backend: ReviewerBackend::OpenRouter,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let global = BackendKind::OpenRouter {
+            model: "anthropic/claude-sonnet-4".to_string(),
+            api_key: "key".to_string(),
+        };
+
+        let settings = cfg.settings(&global, false);
+
+        assert!(matches!(settings.backend, BackendKind::Claude));
+        assert_eq!(settings.reviewer.api_key.as_deref(), Some("key"));
+    }
+
     #[test]
     fn ensure_project_registered_writes_sorted_projects() {
         with_config_home("write", |config_home| {
@@ -113,6 +641,10 @@ mod tests {
             );
             assert!(contents.contains("dir = \"/repo/alpha\""));
             assert!(contents.contains("dir = \"/repo/zeta\""));
+            assert!(
+                !contents.contains("worktree"),
+                "default setup is not written"
+            );
         });
     }
 
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, RwLock};
 
@@ -8,7 +8,16 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::watch;
 use tracing::{info, warn};
 
+use crate::events::{EventHub, OrchestratorEvent};
 use crate::runtime::GlobalLimits;
+use crate::state;
+
+/// How long a `Subscribe` request waits for new events before returning empty.
+pub const SUBSCRIBE_WAIT: std::time::Duration = std::time::Duration::from_secs(25);
+/// How long `Status` waits for the project runtime to describe its agents.
+const STATUS_REPLY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+/// Cancelling stops the agent and removes its worktree before the runtime replies.
+const CANCEL_REPLY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 
 /// Returns the path for the global control Unix socket.
 /// Uses ~/.claude/orchestrator/ so it's accessible inside bwrap sandboxes.
@@ -37,15 +46,46 @@ pub enum ControlRequest {
         project: String,
         task_id: String,
     },
+    /// A task went back to `ready` outside the runtime (e.g. an answered question).
+    NotifyTaskReady {
+        project: String,
+        task_id: String,
+    },
     SetConcurrency {
         max: u8,
     },
+    /// Per-project cap (None removes it) and optional fair-share priority.
+    SetProjectConcurrency {
+        project: String,
+        max: Option<u8>,
+        priority: Option<u32>,
+    },
     Abort {
         project: String,
     },
+    /// Stop starting new agents for a project; running agents continue.
+    Pause {
+        project: String,
+    },
+    Resume {
+        project: String,
+    },
+    /// Stop dispatching everywhere and exit once in-flight agents finish.
+    Drain,
+    /// Stop one task's agent; the task goes back to `ready` or to `cancelled`.
+    CancelTask {
+        project: String,
+        task_id: String,
+        requeue: bool,
+    },
     Status {
         project: String,
     },
+    /// Long-poll for events newer than `after` (None = only events from now on).
+    Subscribe {
+        project: Option<String>,
+        after: Option<u64>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -57,18 +97,76 @@ pub enum ControlResponse {
     Status {
         agents: Vec<AgentStatus>,
         project: String,
+        /// Task count per status, e.g. `ready`, `in_progress`.
+        #[serde(default)]
+        task_counts: BTreeMap<String, usize>,
+        #[serde(default)]
+        slots: SlotUsage,
+        #[serde(default)]
+        paused: bool,
+        #[serde(default)]
+        draining: bool,
+    },
+    /// Events in sequence order; pass `cursor` as `after` on the next request.
+    /// `missed` counts events that fell out of the buffer before this
+    /// subscriber saw them.
+    Events {
+        events: Vec<OrchestratorEvent>,
+        cursor: u64,
+        #[serde(default)]
+        missed: u64,
     },
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// An agent on the project bus. The task fields are only filled for task
+/// agents the runtime is tracking; the rest come from the bus alone.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct AgentStatus {
     pub name: String,
     pub role: String,
+    #[serde(default)]
+    pub task_id: Option<String>,
+    #[serde(default)]
+    pub task_title: Option<String>,
+    /// Seconds since the task was claimed.
+    #[serde(default)]
+    pub claimed_secs: Option<u64>,
+    /// Seconds since the agent's last heartbeat.
+    #[serde(default)]
+    pub idle_secs: Option<u64>,
+    /// 1-based attempt number.
+    #[serde(default)]
+    pub attempt: Option<u32>,
+    #[serde(default)]
+    pub worktree: Option<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub backend: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// A runtime's reply to `status_request`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RuntimeStatus {
+    pub agents: Vec<AgentStatus>,
+    pub task_counts: BTreeMap<String, usize>,
+}
+
+/// Agent slot usage, globally and for the requested project.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SlotUsage {
+    pub global_active: usize,
+    pub global_max: usize,
+    pub project_active: usize,
+    pub project_max: Option<usize>,
 }
 
 pub async fn run_control_server(
     registry: ProjectRegistry,
     global_limits: Arc<GlobalLimits>,
+    events: Arc<EventHub>,
     shutdown_tx: watch::Sender<bool>,
     mut shutdown_rx: watch::Receiver<bool>,
 ) {
@@ -89,7 +187,7 @@ pub async fn run_control_server(
     loop {
         tokio::select! {
             result = server.accept() => {
-                handle_accept_result(result, &registry, &global_limits, &shutdown_tx);
+                handle_accept_result(result, &registry, &global_limits, &events, &shutdown_tx);
             }
             _ = shutdown_rx.changed() => {
                 info!("Control server shutting down");
@@ -103,6 +201,7 @@ fn handle_accept_result(
     result: Result<(Connection, CallerInfo), IpcError>,
     registry: &ProjectRegistry,
     global_limits: &Arc<GlobalLimits>,
+    events: &Arc<EventHub>,
     shutdown_tx: &watch::Sender<bool>,
 ) {
     let (conn, caller) = match result {
@@ -116,21 +215,23 @@ fn handle_accept_result(
         "Control connection from pid={} uid={}",
         caller.pid, caller.uid
     );
-    spawn_control_connection(conn, registry, global_limits, shutdown_tx);
+    spawn_control_connection(conn, registry, global_limits, events, shutdown_tx);
 }
 
 fn spawn_control_connection(
     mut conn: Connection,
     registry: &ProjectRegistry,
     global_limits: &Arc<GlobalLimits>,
+    events: &Arc<EventHub>,
     shutdown_tx: &watch::Sender<bool>,
 ) {
     let registry = registry.clone();
     let global_limits = global_limits.clone();
+    let events = events.clone();
     let shutdown_tx = shutdown_tx.clone();
     tokio::spawn(async move {
         if let Err(error) =
-            handle_connection(&mut conn, &registry, &global_limits, &shutdown_tx).await
+            handle_connection(&mut conn, &registry, &global_limits, &events, &shutdown_tx).await
         {
             warn!("Control connection error: {error}");
         }
@@ -141,10 +242,31 @@ async fn handle_connection(
     conn: &mut peercred_ipc::Connection,
     registry: &ProjectRegistry,
     global_limits: &Arc<GlobalLimits>,
+    events: &EventHub,
     shutdown_tx: &watch::Sender<bool>,
 ) -> anyhow::Result<()> {
     let request: ControlRequest = conn.read().await?;
-    let response = handle_request(request, registry, global_limits, shutdown_tx);
+    let response = match request {
+        ControlRequest::Subscribe { project, after } => {
+            let batch = events
+                .wait_after(after, project.as_deref(), SUBSCRIBE_WAIT)
+                .await;
+            ControlResponse::Events {
+                events: batch.events,
+                cursor: batch.cursor,
+                missed: batch.missed,
+            }
+        }
+        ControlRequest::Status { project } => {
+            status_response(registry, global_limits, project).await
+        }
+        ControlRequest::CancelTask {
+            project,
+            task_id,
+            requeue,
+        } => cancel_response(registry, &project, task_id, requeue).await,
+        request => handle_request(request, registry, global_limits, shutdown_tx),
+    };
     conn.write(&response).await?;
     Ok(())
 }
@@ -162,14 +284,44 @@ fn handle_request(
             content,
         } => with_bus(registry, &project, |bus| send_to_agent(bus, &to, &content)),
         ControlRequest::SetConcurrency { max } => set_concurrency(global_limits, max),
+        ControlRequest::SetProjectConcurrency {
+            project,
+            max,
+            priority,
+        } => with_bus(registry, &project, |_bus| {
+            set_project_concurrency(global_limits, &project, max, priority)
+        }),
         ControlRequest::NotifyTaskCreated { project, task_id } => {
             notify_task_created(registry, &project, task_id)
         }
+        ControlRequest::NotifyTaskReady { project, task_id } => {
+            with_bus(registry, &project, |bus| {
+                let payload = serde_json::json!({ "task_id": task_id });
+                send_bus_message(bus, "runtime", "task_ready", payload)
+            })
+        }
+        ControlRequest::Pause { project } => with_bus(registry, &project, |_bus| {
+            set_paused(global_limits, &project, true)
+        }),
+        ControlRequest::Resume { project } => with_bus(registry, &project, |bus| {
+            let response = set_paused(global_limits, &project, false);
+            let _ = send_bus_message(bus, "runtime", "dispatch_resumed", serde_json::json!({}));
+            response
+        }),
+        ControlRequest::Drain => {
+            global_limits.start_draining();
+            info!("Draining: no new agents, exiting once in-flight agents finish");
+            ControlResponse::Ok
+        }
         ControlRequest::Abort { project } => with_bus(registry, &project, |_bus| {
             let _ = shutdown_tx.send(true);
             ControlResponse::Ok
         }),
-        ControlRequest::Status { project } => status_response(registry, project),
+        ControlRequest::Status { .. }
+        | ControlRequest::Subscribe { .. }
+        | ControlRequest::CancelTask { .. } => ControlResponse::Error {
+            message: "handled by the connection loop".to_string(),
+        },
     }
 }
 
@@ -180,6 +332,36 @@ fn set_concurrency(global_limits: &Arc<GlobalLimits>, max: u8) -> ControlRespons
     ControlResponse::Ok
 }
 
+fn set_project_concurrency(
+    global_limits: &Arc<GlobalLimits>,
+    project: &str,
+    max: Option<u8>,
+    priority: Option<u32>,
+) -> ControlResponse {
+    let max = max.map(|m| (m as usize).clamp(1, 20));
+    global_limits.set_project_quota(project, max, priority);
+    info!(
+        "Project '{}' max concurrency set to {:?} (priority {:?})",
+        project, max, priority
+    );
+    ControlResponse::Ok
+}
+
+fn set_paused(global_limits: &GlobalLimits, project: &str, paused: bool) -> ControlResponse {
+    global_limits.set_paused(project, paused);
+    info!(
+        "Project '{}' dispatching {}",
+        project,
+        if paused { "paused" } else { "resumed" }
+    );
+    match state::set_paused(project, paused) {
+        Ok(()) => ControlResponse::Ok,
+        Err(error) => ControlResponse::Error {
+            message: format!("applied, but not saved for restarts: {error:#}"),
+        },
+    }
+}
+
 fn notify_task_created(
     registry: &ProjectRegistry,
     project: &str,
@@ -191,26 +373,139 @@ fn notify_task_created(
     })
 }
 
-fn status_response(registry: &ProjectRegistry, project: String) -> ControlResponse {
-    let agents = project_agents(registry, &project);
-    ControlResponse::Status { agents, project }
+async fn status_response(
+    registry: &ProjectRegistry,
+    global_limits: &GlobalLimits,
+    project: String,
+) -> ControlResponse {
+    let bus = registry.read().unwrap().get(&project).cloned();
+    let Some(bus) = bus else {
+        return ControlResponse::Status {
+            agents: Vec::new(),
+            project,
+            task_counts: BTreeMap::new(),
+            slots: slot_usage(global_limits, ""),
+            paused: false,
+            draining: global_limits.is_draining(),
+        };
+    };
+    // An unresponsive runtime still gets the bus-only listing.
+    let runtime = query_runtime_status(&bus).await.unwrap_or_default();
+    ControlResponse::Status {
+        agents: project_agents(&bus, runtime.agents),
+        slots: slot_usage(global_limits, &project),
+        task_counts: runtime.task_counts,
+        paused: global_limits.is_paused(&project),
+        draining: global_limits.is_draining(),
+        project,
+    }
 }
 
-fn project_agents(registry: &ProjectRegistry, project: &str) -> Vec<AgentStatus> {
-    let guard = registry.read().unwrap();
-    let Some(bus) = guard.get(project) else {
-        return Vec::new();
+async fn query_runtime_status(bus: &Bus) -> Option<RuntimeStatus> {
+    let reply = ask_runtime(
+        bus,
+        "status_request",
+        serde_json::json!({}),
+        "status_reply",
+        STATUS_REPLY_TIMEOUT,
+    )
+    .await?;
+    serde_json::from_value(reply).ok()
+}
+
+/// Have the runtime cancel the task and report the outcome it replies with.
+async fn cancel_response(
+    registry: &ProjectRegistry,
+    project: &str,
+    task_id: String,
+    requeue: bool,
+) -> ControlResponse {
+    let bus = registry.read().unwrap().get(project).cloned();
+    let Some(bus) = bus else {
+        return ControlResponse::Error {
+            message: format!("unknown project: {project}"),
+        };
     };
-    bus.list_registered()
+    let payload = serde_json::json!({ "task_id": task_id, "requeue": requeue });
+    let reply = ask_runtime(
+        &bus,
+        "cancel_task",
+        payload,
+        "cancel_reply",
+        CANCEL_REPLY_TIMEOUT,
+    )
+    .await;
+    match reply {
+        Some(reply) => match reply.get("error").and_then(|e| e.as_str()) {
+            Some(message) => ControlResponse::Error {
+                message: message.to_string(),
+            },
+            None => ControlResponse::Ok,
+        },
+        None => ControlResponse::Error {
+            message: format!("the runtime did not confirm cancelling {task_id}"),
+        },
+    }
+}
+
+/// Send `kind` to the runtime and wait up to `timeout` for its `reply_kind`.
+async fn ask_runtime(
+    bus: &Bus,
+    kind: &str,
+    payload: serde_json::Value,
+    reply_kind: &str,
+    timeout: std::time::Duration,
+) -> Option<serde_json::Value> {
+    let (mut mailbox, name) = register_control_mailbox(bus).ok()?;
+    let reply = async {
+        mailbox.send("runtime", kind, payload).ok()?;
+        while let Some(msg) = mailbox.recv().await {
+            if msg.kind == reply_kind {
+                return Some(msg.payload);
+            }
+        }
+        None
+    };
+    let reply = tokio::time::timeout(timeout, reply).await.ok().flatten();
+    bus.deregister(&name);
+    reply
+}
+
+/// Registered agents, with the runtime's details where it has them.
+fn project_agents(bus: &Bus, detailed: Vec<AgentStatus>) -> Vec<AgentStatus> {
+    let mut detailed: HashMap<String, AgentStatus> = detailed
         .into_iter()
-        .filter(|name| name != "runtime" && !name.starts_with("relay-"))
-        .map(agent_status)
-        .collect()
+        .map(|agent| (agent.name.clone(), agent))
+        .collect();
+    let mut agents: Vec<AgentStatus> = bus
+        .list_registered()
+        .into_iter()
+        .filter(|name| {
+            name != "runtime" && !name.starts_with("relay-") && !name.starts_with("control-")
+        })
+        .map(|name| detailed.remove(&name).unwrap_or_else(|| agent_status(name)))
+        .collect();
+    agents.sort_by(|a, b| a.name.cmp(&b.name));
+    agents
 }
 
 fn agent_status(name: String) -> AgentStatus {
     let role = role_from_name(&name).to_string();
-    AgentStatus { name, role }
+    AgentStatus {
+        name,
+        role,
+        ..Default::default()
+    }
+}
+
+fn slot_usage(global_limits: &GlobalLimits, project: &str) -> SlotUsage {
+    let quota = global_limits.project_quota(project).unwrap_or_default();
+    SlotUsage {
+        global_active: global_limits.active_agents.load(Ordering::Relaxed),
+        global_max: global_limits.max_concurrent.load(Ordering::Relaxed),
+        project_active: quota.active,
+        project_max: quota.max,
+    }
 }
 
 fn with_bus(
@@ -243,7 +538,7 @@ fn send_bus_message(
     payload: serde_json::Value,
 ) -> ControlResponse {
     let mailbox = match register_control_mailbox(bus) {
-        Ok(mailbox) => mailbox,
+        Ok((mailbox, _name)) => mailbox,
         Err(message) => return ControlResponse::Error { message },
     };
     match mailbox.send(to, kind, payload) {
@@ -254,14 +549,15 @@ fn send_bus_message(
     }
 }
 
-fn register_control_mailbox(bus: &Bus) -> Result<agent_bus::Mailbox, String> {
+fn register_control_mailbox(bus: &Bus) -> Result<(agent_bus::Mailbox, String), String> {
     let base_name = format!("control-{}", std::process::id());
     if let Ok(mailbox) = bus.register(&base_name) {
-        return Ok(mailbox);
+        return Ok((mailbox, base_name));
     }
 
     let fallback_name = format!("{base_name}-{}", timestamp_suffix());
     bus.register(&fallback_name)
+        .map(|mailbox| (mailbox, fallback_name))
         .map_err(|error| format!("Bus register: {error}"))
 }
 
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use tokio::signal::unix::{SignalKind, signal};
@@ -11,7 +12,9 @@ use tracing::{info, warn};
 use crate::agent::BackendKind;
 use crate::config::{self, ProjectConfig};
 use crate::control::{self, ProjectRegistry};
-use crate::runtime::{GlobalLimits, OrchestratorRuntime};
+use crate::events::EventHub;
+use crate::runtime::{GlobalLimits, MERGER_SHUTDOWN_TIMEOUT, OrchestratorRuntime};
+use crate::state::DaemonState;
 
 pub async fn run(backend: BackendKind, no_sandbox: bool) -> Result<()> {
     let projects = config::load_config().context("Failed to load project config")?;
@@ -26,32 +29,112 @@ pub async fn run(backend: BackendKind, no_sandbox: bool) -> Result<()> {
 
     let registry = control::new_registry();
     let global_limits = Arc::new(GlobalLimits::new(10));
+    for project in DaemonState::load().paused {
+        info!("Project '{}' dispatching paused (saved state)", project);
+        global_limits.set_paused(&project, true);
+    }
+    let events = Arc::new(EventHub::new());
     let (global_shutdown_tx, shutdown_rx) = watch::channel(false);
 
     tokio::spawn(control::run_control_server(
         registry.clone(),
         global_limits.clone(),
+        events.clone(),
         global_shutdown_tx.clone(),
         shutdown_rx,
     ));
     tokio::time::sleep(Duration::from_millis(50)).await;
 
-    let mut supervisor = Supervisor::new(registry, global_limits, backend, no_sandbox);
+    let mut supervisor = Supervisor::new(registry, global_limits, events, backend, no_sandbox);
     supervisor.start_all(projects).await;
     supervisor.wait_for_signal(global_shutdown_tx).await;
     info!("Daemon stopped");
     Ok(())
 }
 
+/// Delay before the first restart of a crashed or failed project runtime.
+const RESTART_BACKOFF_BASE: Duration = Duration::from_secs(2);
+/// Upper bound on the restart delay.
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+/// A runtime that stayed up this long resets its failure count when it crashes.
+const HEALTHY_UPTIME: Duration = Duration::from_secs(10 * 60);
+/// How long a stopping runtime may take, beyond its agents' grace periods, before it is aborted.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
 struct ProjectHandle {
     handle: JoinHandle<()>,
     shutdown_tx: watch::Sender<bool>,
+    config: ProjectConfig,
+    started_at: Instant,
+}
+
+/// A runtime that was told to shut down and is finishing its agents.
+struct StoppingProject {
+    handle: JoinHandle<()>,
+    deadline: Instant,
+}
+
+/// Restart bookkeeping for a project whose runtime crashed or failed to start.
+struct RestartState {
+    failures: u32,
+    next_attempt: Instant,
+}
+
+/// What the supervisor must do to bring running projects in line with projects.toml.
+#[derive(Debug, PartialEq, Eq)]
+enum ReconcileAction {
+    Start(String),
+    Stop(String),
+    Restart(String),
+}
+
+/// Compare running projects with the configured ones. Output is sorted by name.
+fn plan_reconcile(
+    running: &HashMap<String, ProjectConfig>,
+    desired: &HashMap<String, ProjectConfig>,
+) -> Vec<ReconcileAction> {
+    let mut actions: Vec<ReconcileAction> = running
+        .keys()
+        .filter(|name| !desired.contains_key(*name))
+        .map(|name| ReconcileAction::Stop(name.clone()))
+        .collect();
+    for (name, config) in desired {
+        match running.get(name) {
+            None => actions.push(ReconcileAction::Start(name.clone())),
+            Some(current) if current != config => {
+                actions.push(ReconcileAction::Restart(name.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    actions.sort_by(|a, b| action_name(a).cmp(action_name(b)));
+    actions
+}
+
+fn action_name(action: &ReconcileAction) -> &str {
+    match action {
+        ReconcileAction::Start(name)
+        | ReconcileAction::Stop(name)
+        | ReconcileAction::Restart(name) => name,
+    }
+}
+
+/// Exponential restart delay after `failures` consecutive failures (1-based).
+fn restart_backoff(failures: u32) -> Duration {
+    let exponent = failures.saturating_sub(1).min(16);
+    RESTART_BACKOFF_BASE
+        .saturating_mul(1 << exponent)
+        .min(RESTART_BACKOFF_MAX)
 }
 
 struct Supervisor {
     projects: HashMap<String, ProjectHandle>,
+    /// Removed or changed projects whose old runtime is still shutting down.
+    stopping: HashMap<String, StoppingProject>,
+    restarts: HashMap<String, RestartState>,
     registry: ProjectRegistry,
     global_limits: Arc<GlobalLimits>,
+    events: Arc<EventHub>,
     backend: BackendKind,
     no_sandbox: bool,
 }
@@ -60,13 +143,17 @@ impl Supervisor {
     fn new(
         registry: ProjectRegistry,
         global_limits: Arc<GlobalLimits>,
+        events: Arc<EventHub>,
         backend: BackendKind,
         no_sandbox: bool,
     ) -> Self {
         Self {
             projects: HashMap::new(),
+            stopping: HashMap::new(),
+            restarts: HashMap::new(),
             registry,
             global_limits,
+            events,
             backend,
             no_sandbox,
         }
@@ -78,6 +165,9 @@ impl Supervisor {
         }
     }
 
+    /// Reconcile running runtimes with projects.toml: stop removed projects,
+    /// restart changed ones, and (re)start missing or crashed ones with backoff.
+    /// Stops don't block: a changed project starts again once its old runtime exits.
     async fn sync_projects_from_disk(&mut self) {
         let configs = match config::load_config() {
             Ok(cfg) => cfg,
@@ -87,29 +177,107 @@ impl Supervisor {
             }
         };
 
-        for (name, cfg) in configs {
-            if self.projects.contains_key(&name) {
+        self.reap_stopped();
+        self.reap_crashed();
+        self.restarts.retain(|name, _| configs.contains_key(name));
+
+        let running: HashMap<String, ProjectConfig> = self
+            .projects
+            .iter()
+            .map(|(name, ph)| (name.clone(), ph.config.clone()))
+            .collect();
+        for action in plan_reconcile(&running, &configs) {
+            match action {
+                ReconcileAction::Stop(name) => {
+                    info!("Project '{}' removed from config, stopping runtime", name);
+                    self.stop_project(&name);
+                }
+                ReconcileAction::Restart(name) => {
+                    info!("Project '{}' config changed, restarting runtime", name);
+                    self.stop_project(&name);
+                    self.restarts.remove(&name);
+                }
+                ReconcileAction::Start(name) => {
+                    if self.stopping.contains_key(&name) {
+                        continue;
+                    }
+                    if self
+                        .restarts
+                        .get(&name)
+                        .is_some_and(|r| Instant::now() < r.next_attempt)
+                    {
+                        continue;
+                    }
+                    if self.restarts.contains_key(&name) {
+                        info!("Restarting runtime for project '{}'", name);
+                    } else {
+                        info!("Starting runtime for project '{}'", name);
+                    }
+                    self.start_project(name.clone(), configs[&name].clone())
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Drop handles of runtimes that exited on their own and schedule a restart.
+    fn reap_crashed(&mut self) {
+        let crashed: Vec<String> = self
+            .projects
+            .iter()
+            .filter(|(_, ph)| ph.handle.is_finished())
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in crashed {
+            let Some(ph) = self.projects.remove(&name) else {
                 continue;
+            };
+            self.registry.write().unwrap().remove(&name);
+            if ph.started_at.elapsed() >= HEALTHY_UPTIME {
+                self.restarts.remove(&name);
             }
-            info!("Discovered new project '{}', starting runtime", name);
-            self.start_project(name, cfg).await;
+            let delay = self.record_failure(&name);
+            warn!(
+                "Runtime for project '{}' exited unexpectedly, restarting in {:?}",
+                name, delay
+            );
         }
     }
 
+    fn record_failure(&mut self, name: &str) -> Duration {
+        let state = self
+            .restarts
+            .entry(name.to_string())
+            .or_insert(RestartState {
+                failures: 0,
+                next_attempt: Instant::now(),
+            });
+        state.failures += 1;
+        let delay = restart_backoff(state.failures);
+        state.next_attempt = Instant::now() + delay;
+        delay
+    }
+
     async fn start_project(&mut self, name: String, config: ProjectConfig) {
         let db_path = db_path_for_project(&name);
         let runtime = match OrchestratorRuntime::new(
             &db_path,
             config.dir.clone(),
-            self.backend.clone(),
-            self.no_sandbox,
+            config.settings(&self.backend, self.no_sandbox),
             self.global_limits.clone(),
+            self.events.clone(),
         )
         .await
         {
             Ok(r) => r,
             Err(e) => {
-                tracing::error!("Failed to create runtime '{}': {}", name, e);
+                let delay = self.record_failure(&name);
+                tracing::error!(
+                    "Failed to create runtime '{}': {} (retrying in {:?})",
+                    name,
+                    e,
+                    delay
+                );
                 return;
             }
         };
@@ -135,10 +303,46 @@ impl Supervisor {
             ProjectHandle {
                 handle,
                 shutdown_tx,
+                config,
+                started_at: Instant::now(),
             },
         );
     }
 
+    /// Tell one project runtime to shut down. `reap_stopped` collects it once
+    /// it exits, or aborts it when its stop timeout passes.
+    fn stop_project(&mut self, name: &str) {
+        let Some(ph) = self.projects.remove(name) else {
+            return;
+        };
+        self.registry.write().unwrap().remove(name);
+        let _ = ph.shutdown_tx.send(true);
+        let deadline = Instant::now() + self.stop_timeout(&ph.config);
+        self.stopping.insert(
+            name.to_string(),
+            StoppingProject {
+                handle: ph.handle,
+                deadline,
+            },
+        );
+    }
+
+    /// Drop stopping runtimes that exited and abort the ones past their deadline.
+    fn reap_stopped(&mut self) {
+        self.stopping.retain(|name, stopping| {
+            if stopping.handle.is_finished() {
+                info!("Project '{}' stopped", name);
+                return false;
+            }
+            if Instant::now() < stopping.deadline {
+                return true;
+            }
+            warn!("Project '{}' stop timed out, aborting", name);
+            stopping.handle.abort();
+            false
+        });
+    }
+
     async fn wait_for_signal(&mut self, global_shutdown_tx: watch::Sender<bool>) {
         let mut sigint = signal(SignalKind::interrupt()).expect("failed to register SIGINT");
         let mut sigterm = signal(SignalKind::terminate()).expect("failed to register SIGTERM");
@@ -149,8 +353,11 @@ impl Supervisor {
                 loop {
                     reload.tick().await;
                     self.sync_projects_from_disk().await;
+                    if self.drain_complete() {
+                        break;
+                    }
                 }
-            } => unreachable!(),
+            } => info!("Drain complete, no agents, reviews or merges running"),
             _ = sigint.recv() => info!("Received SIGINT"),
             _ = sigterm.recv() => info!("Received SIGTERM"),
         }
@@ -159,6 +366,19 @@ impl Supervisor {
         self.shutdown_all().await;
     }
 
+    /// Time a runtime gets to stop: agent grace period, then the merger, then cleanup.
+    fn stop_timeout(&self, config: &ProjectConfig) -> Duration {
+        let settings = config.settings(&self.backend, self.no_sandbox);
+        settings.shutdown_grace + MERGER_SHUTDOWN_TIMEOUT + STOP_TIMEOUT
+    }
+
+    /// Draining, with no agents running and no reviews or merges in flight.
+    fn drain_complete(&self) -> bool {
+        self.global_limits.is_draining()
+            && self.global_limits.active_agents.load(Ordering::Relaxed) == 0
+            && !self.global_limits.has_background_work()
+    }
+
     async fn shutdown_all(&mut self) {
         let names: Vec<String> = self.projects.keys().cloned().collect();
         info!("Shutting down {} project(s)", names.len());
@@ -172,12 +392,21 @@ impl Supervisor {
 
         for name in &names {
             if let Some(ph) = self.projects.remove(name) {
-                match tokio::time::timeout(Duration::from_secs(5), ph.handle).await {
+                let timeout = self.stop_timeout(&ph.config);
+                match tokio::time::timeout(timeout, ph.handle).await {
                     Ok(_) => info!("Project '{}' shut down cleanly", name),
                     Err(_) => warn!("Project '{}' shutdown timed out, aborting", name),
                 }
             }
         }
+
+        for (name, stopping) in self.stopping.drain() {
+            let deadline = tokio::time::Instant::from_std(stopping.deadline);
+            match tokio::time::timeout_at(deadline, stopping.handle).await {
+                Ok(_) => info!("Project '{}' stopped", name),
+                Err(_) => warn!("Project '{}' stop timed out, aborting", name),
+            }
+        }
     }
 }
 
@@ -187,3 +416,104 @@ pub fn db_path_for_project(project: &str) -> std::path::PathBuf {
         .join(project)
         .join("tasks.db")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(dir: &str) -> ProjectConfig {
+        ProjectConfig {
+            dir: dir.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn plan_reconcile_starts_stops_and_restarts() {
+        let running = HashMap::from([
+            ("kept".to_string(), project("/repo/kept")),
+            ("moved".to_string(), project("/repo/old")),
+            ("removed".to_string(), project("/repo/removed")),
+        ]);
+        let desired = HashMap::from([
+            ("added".to_string(), project("/repo/added")),
+            ("kept".to_string(), project("/repo/kept")),
+            ("moved".to_string(), project("/repo/new")),
+        ]);
+
+        assert_eq!(
+            plan_reconcile(&running, &desired),
+            vec![
+                ReconcileAction::Start("added".to_string()),
+                ReconcileAction::Restart("moved".to_string()),
+                ReconcileAction::Stop("removed".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_reconcile_restarts_on_setting_change() {
+        let running = HashMap::from([("p".to_string(), project("/repo/p"))]);
+        let mut changed = project("/repo/p");
+        changed.max_agents = Some(2);
+        let desired = HashMap::from([("p".to_string(), changed)]);
+
+        assert_eq!(
+            plan_reconcile(&running, &desired),
+            vec![ReconcileAction::Restart("p".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn stopping_project_is_kept_until_it_exits_or_times_out() {
+        let mut supervisor = Supervisor::new(
+            control::new_registry(),
+            Arc::new(GlobalLimits::new(1)),
+            Arc::new(EventHub::new()),
+            BackendKind::Claude,
+            true,
+        );
+        let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+        let handle = tokio::spawn(async move {
+            let _ = shutdown_rx.changed().await;
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        });
+        supervisor.projects.insert(
+            "p".to_string(),
+            ProjectHandle {
+                handle,
+                shutdown_tx,
+                config: project("/repo/p"),
+                started_at: Instant::now(),
+            },
+        );
+
+        supervisor.stop_project("p");
+        supervisor.reap_stopped();
+        assert!(supervisor.projects.is_empty());
+        assert!(supervisor.stopping.contains_key("p"));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        supervisor.reap_stopped();
+        assert!(supervisor.stopping.is_empty());
+
+        supervisor.stopping.insert(
+            "stuck".to_string(),
+            StoppingProject {
+                handle: tokio::spawn(std::future::pending()),
+                deadline: Instant::now(),
+            },
+        );
+        supervisor.reap_stopped();
+        assert!(supervisor.stopping.is_empty());
+    }
+
+    #[test]
+    fn restart_backoff_doubles_and_caps() {
+        assert_eq!(restart_backoff(1), Duration::from_secs(2));
+        assert_eq!(restart_backoff(2), Duration::from_secs(4));
+        assert_eq!(restart_backoff(4), Duration::from_secs(16));
+        assert_eq!(restart_backoff(20), RESTART_BACKOFF_MAX);
+        assert_eq!(restart_backoff(u32::MAX), RESTART_BACKOFF_MAX);
+    }
+}
@@ -4,18 +4,97 @@
 //! It finds ready (unassigned) tasks and returns task IDs to spawn agents for.
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use agent_bus::Mailbox;
-use llm_tasks::db::{Database, TaskUpdates};
+use llm_tasks::db::{Database, Event, Task, TaskUpdates};
+use serde::{Deserialize, Serialize};
 
-/// How long a task agent can be idle before its task is reclaimed.
+use crate::config::ProjectSettings;
+use crate::runtime_support as support;
+
+mod persist;
+
+pub use persist::{DispatchState, SavedAssignment};
+
+/// Default for how long a task agent can be idle before its task is reclaimed.
 pub const AGENT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// Under the priority policy a waiting task gains one priority level per step.
+pub const PRIORITY_AGING_STEP: Duration = Duration::from_secs(2 * 60 * 60);
+/// Prefix of the comment holding a blocked agent's question.
+pub const QUESTION_PREFIX: &str = "Question: ";
+/// Prefix of the comment holding the answer that unblocked the task.
+pub const ANSWER_PREFIX: &str = "Answer: ";
+
+/// Order in which ready tasks are handed to agents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DispatchPolicy {
+    /// Oldest task first.
+    Fifo,
+    /// Highest priority (3 = high .. 0 = none) first, aged by waiting time, then oldest.
+    #[default]
+    Priority,
+}
+
+/// The parts of a ready task that dispatch ordering looks at.
+#[derive(Clone, Debug)]
+pub struct DispatchCandidate {
+    pub task_id: String,
+    pub priority: i64,
+    /// Creation time in Unix seconds (None if unparseable: treated as newest).
+    pub created_at: Option<u64>,
+}
+
+impl DispatchCandidate {
+    fn from_task(task: &Task) -> Self {
+        Self {
+            task_id: task.id.clone(),
+            priority: i64::from(task.priority),
+            created_at: support::parse_db_timestamp(&task.created_at),
+        }
+    }
+
+    /// Priority plus one level per `PRIORITY_AGING_STEP` spent waiting.
+    fn effective_priority(&self, now: u64) -> i64 {
+        let waited = self
+            .created_at
+            .map_or(0, |created| now.saturating_sub(created));
+        self.priority + (waited / PRIORITY_AGING_STEP.as_secs()) as i64
+    }
+}
+
+/// Sort candidates for dispatch. Ties keep the database order.
+pub fn rank_candidates(candidates: &mut [DispatchCandidate], policy: DispatchPolicy, now: u64) {
+    let age_key = |c: &DispatchCandidate| c.created_at.unwrap_or(u64::MAX);
+    match policy {
+        DispatchPolicy::Fifo => candidates.sort_by_key(age_key),
+        DispatchPolicy::Priority => candidates.sort_by(|a, b| {
+            b.effective_priority(now)
+                .cmp(&a.effective_priority(now))
+                .then_with(|| age_key(a).cmp(&age_key(b)))
+        }),
+    }
+}
 
 struct TaskAssignment {
     agent_name: String,
+    claimed_at: Instant,
     last_activity: Instant,
+    /// 1-based attempt number of this dispatch.
+    attempt: u32,
+}
+
+/// Snapshot of one active assignment, for status reporting.
+#[derive(Clone, Debug)]
+pub struct AssignmentInfo {
+    pub task_id: String,
+    pub agent_name: String,
+    pub since_claim: Duration,
+    pub idle: Duration,
+    pub attempt: u32,
 }
 
 /// Tracks active task agents and handles dispatch + state transitions.
@@ -24,35 +103,94 @@ pub struct Dispatcher {
     mailbox: Mailbox,
     /// task_id → assignment (agent_name + last activity)
     active_tasks: HashMap<String, TaskAssignment>,
+    /// Idle time after which a task is reclaimed from its agent.
+    idle_timeout: Duration,
+    policy: DispatchPolicy,
+    /// Backend name recorded with saved sessions.
+    backend: &'static str,
+    /// Where assignments are saved for the next daemon start (None = memory only).
+    state_path: Option<PathBuf>,
+    last_saved: Option<Instant>,
 }
 
 impl Dispatcher {
-    pub fn new(db: Arc<Database>, mailbox: Mailbox) -> Self {
+    pub fn new(db: Arc<Database>, mailbox: Mailbox, settings: &ProjectSettings) -> Self {
         Self {
             db,
             mailbox,
             active_tasks: HashMap::new(),
+            idle_timeout: settings.idle_timeout,
+            policy: settings.dispatch_policy,
+            backend: settings.backend.name(),
+            state_path: None,
+            last_saved: None,
         }
     }
 
+    /// Persist assignments to `path` whenever they change.
+    pub fn persist_to(mut self, path: PathBuf) -> Self {
+        self.state_path = Some(path);
+        self
+    }
+
+    /// Assignments saved by the previous daemon run, if any.
+    pub fn load_saved_state(&self) -> Option<DispatchState> {
+        let path = self.state_path.as_ref()?;
+        DispatchState::load(path).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring saved dispatch state: {:#}", e);
+            None
+        })
+    }
+
+    /// Record that the runtime is stopping on purpose, not crashing.
+    pub fn mark_clean_shutdown(&mut self) {
+        self.save_state(true);
+    }
+
+    fn save_state(&mut self, clean_shutdown: bool) {
+        let Some(path) = &self.state_path else {
+            return;
+        };
+        let now = support::unix_now();
+        let assignments = self
+            .active_tasks
+            .iter()
+            .map(|(task_id, a)| SavedAssignment {
+                task_id: task_id.clone(),
+                agent_name: a.agent_name.clone(),
+                claimed_at: persist::unix_at(a.claimed_at, now),
+                last_heartbeat: persist::unix_at(a.last_activity, now),
+                attempt: a.attempt,
+                session_key: a.agent_name.clone(),
+                backend: self.backend.to_string(),
+            })
+            .collect();
+        let state = DispatchState {
+            saved_at: now,
+            clean_shutdown,
+            assignments,
+        };
+        if let Err(e) = state.save(path) {
+            tracing::warn!("Failed to save dispatch state: {:#}", e);
+        }
+        self.last_saved = Some(Instant::now());
+    }
+
     /// Agent signals task complete → set in_review. Returns task_id for review.
     /// If the task is `pending_delete`, skips the review and closes it immediately.
     pub async fn handle_agent_complete(&mut self, task_id: &str, content: &str) -> bool {
         if self.active_tasks.remove(task_id).is_none() {
             return false;
         }
+        self.save_state(false);
         if self.is_pending_delete(task_id).await {
             tracing::info!("Task {} is pending_delete, skipping review", task_id);
             let _ = self.db.close_task(task_id, "runtime").await;
             return false;
         }
         // Add agent output as comment
-        let short = if content.len() > 2000 {
-            &content[..2000]
-        } else {
-            content
-        };
-        let _ = self.db.add_comment(task_id, "agent", short).await;
+        let short = claude_architect::truncate(content, 2000);
+        let _ = self.db.add_comment(task_id, "agent", &short).await;
         self.transition_to_review(task_id).await;
         true
     }
@@ -62,6 +200,7 @@ impl Dispatcher {
         if self.active_tasks.remove(task_id).is_none() {
             return;
         }
+        self.save_state(false);
         self.transition_to_needs_info(task_id, content).await;
     }
 
@@ -71,6 +210,12 @@ impl Dispatcher {
             && let Some(assignment) = self.active_tasks.get_mut(&task_id)
         {
             assignment.last_activity = Instant::now();
+            let save_due = self
+                .last_saved
+                .is_none_or(|t| t.elapsed() >= persist::HEARTBEAT_SAVE_INTERVAL);
+            if save_due {
+                self.save_state(false);
+            }
         }
     }
 
@@ -79,7 +224,7 @@ impl Dispatcher {
         let timed_out: Vec<(String, String)> = self
             .active_tasks
             .iter()
-            .filter(|(_, a)| a.last_activity.elapsed() > AGENT_IDLE_TIMEOUT)
+            .filter(|(_, a)| a.last_activity.elapsed() > self.idle_timeout)
             .map(|(tid, a)| (tid.clone(), a.agent_name.clone()))
             .collect();
 
@@ -95,7 +240,7 @@ impl Dispatcher {
                 status: Some("ready"),
                 ..Default::default()
             };
-            if let Err(e) = self.db.update_task(&task_id, updates, "runtime").await {
+            if let Err(e) = self.db.update_task(&task_id, updates, "watchdog").await {
                 tracing::error!(
                     "Failed to reclaim task {} from {}: {}",
                     task_id,
@@ -103,10 +248,13 @@ impl Dispatcher {
                     e
                 );
             }
-            let _ = self.db.clear_assignee(&task_id, "runtime").await;
+            let _ = self.db.clear_assignee(&task_id, "watchdog").await;
             self.active_tasks.remove(&task_id);
             aborted.push(agent_name);
         }
+        if !aborted.is_empty() {
+            self.save_state(false);
+        }
         aborted
     }
 
@@ -122,12 +270,9 @@ impl Dispatcher {
         fixed
     }
 
-    /// Find ready tasks that can be dispatched. Returns task IDs.
-    pub async fn tasks_to_dispatch(&self, max_concurrent: usize) -> Vec<String> {
-        let available = max_concurrent.saturating_sub(self.active_tasks.len());
-        if available == 0 {
-            return Vec::new();
-        }
+    /// Find ready tasks that could be dispatched, in dispatch order.
+    /// The caller decides how many to spawn based on its granted slots.
+    pub async fn tasks_to_dispatch(&self) -> Vec<String> {
         let tasks = match self.db.ready_tasks().await {
             Ok(t) => t,
             Err(e) => {
@@ -135,40 +280,80 @@ impl Dispatcher {
                 return Vec::new();
             }
         };
-        tasks.iter().take(available).map(|t| t.id.clone()).collect()
+        let mut candidates: Vec<DispatchCandidate> = tasks
+            .iter()
+            .filter(|t| !self.active_tasks.contains_key(&t.id))
+            .map(DispatchCandidate::from_task)
+            .collect();
+        rank_candidates(&mut candidates, self.policy, support::unix_now());
+        candidates.into_iter().map(|c| c.task_id).collect()
     }
 
     /// Claim a task in the DB and register it as active.
-    pub async fn claim_and_register(&mut self, task_id: &str, agent_name: &str) -> bool {
+    pub async fn claim_and_register(
+        &mut self,
+        task_id: &str,
+        agent_name: &str,
+        attempt: u32,
+    ) -> bool {
         if let Err(e) = self.db.claim_task(task_id, agent_name).await {
             tracing::warn!("Failed to claim task {} for {}: {}", task_id, agent_name, e);
             return false;
         }
-        self.active_tasks.insert(
-            task_id.to_string(),
-            TaskAssignment {
-                agent_name: agent_name.to_string(),
-                last_activity: Instant::now(),
-            },
-        );
+        self.register_active(task_id.to_string(), agent_name.to_string(), attempt);
         true
     }
 
     /// Register a resumed task agent (from a previous session).
-    pub fn register_active(&mut self, task_id: String, agent_name: String) {
+    pub fn register_active(&mut self, task_id: String, agent_name: String, attempt: u32) {
+        let now = Instant::now();
         self.active_tasks.insert(
             task_id,
             TaskAssignment {
                 agent_name,
-                last_activity: Instant::now(),
+                claimed_at: now,
+                last_activity: now,
+                attempt,
+            },
+        );
+        self.save_state(false);
+    }
+
+    /// Re-register an assignment saved by the previous run, keeping its claim
+    /// time and the idle time it had when saved.
+    pub fn restore_active(&mut self, saved: &SavedAssignment, saved_at: u64) {
+        let since_claim = support::unix_now().saturating_sub(saved.claimed_at);
+        self.active_tasks.insert(
+            saved.task_id.clone(),
+            TaskAssignment {
+                agent_name: saved.agent_name.clone(),
+                claimed_at: persist::instant_ago(Duration::from_secs(since_claim)),
+                last_activity: persist::instant_ago(saved.idle_at_save(saved_at)),
+                attempt: saved.attempt,
             },
         );
+        self.save_state(false);
+    }
+
+    /// Active assignments, for status reporting.
+    pub fn assignments(&self) -> Vec<AssignmentInfo> {
+        self.active_tasks
+            .iter()
+            .map(|(task_id, a)| AssignmentInfo {
+                task_id: task_id.clone(),
+                agent_name: a.agent_name.clone(),
+                since_claim: a.claimed_at.elapsed(),
+                idle: a.last_activity.elapsed(),
+                attempt: a.attempt,
+            })
+            .collect()
     }
 
     /// Remove a task from tracking (e.g. when agent is aborted).
     pub fn remove_task_by_agent(&mut self, agent_name: &str) {
         if let Some(task_id) = self.task_id_for_agent(agent_name) {
             self.active_tasks.remove(&task_id);
+            self.save_state(false);
         }
     }
 
@@ -306,8 +491,142 @@ impl Dispatcher {
         if let Err(e) = self.db.update_task(task_id, updates, "runtime").await {
             tracing::error!("Failed to set task {} needs_info: {}", task_id, e);
         }
-        if let Err(e) = self.db.add_comment(task_id, "agent", question).await {
+        let comment = format!("{QUESTION_PREFIX}{question}");
+        if let Err(e) = self.db.add_comment(task_id, "agent", &comment).await {
             tracing::error!("Failed to add needs_info comment on {}: {}", task_id, e);
         }
     }
 }
+
+/// Record the answer to a `needs_info` task's question and put the task back
+/// to `ready`. The answer reaches the next attempt through the task comments.
+pub async fn record_answer(
+    db: &Database,
+    task_id: &str,
+    answer: &str,
+    actor: &str,
+) -> anyhow::Result<()> {
+    let answer = answer.trim();
+    if answer.is_empty() {
+        anyhow::bail!("answer is empty");
+    }
+    let task = db.get_task(task_id).await?;
+    if task.status != "needs_info" {
+        anyhow::bail!("task {} is {}, not needs_info", task_id, task.status);
+    }
+    db.add_comment(task_id, actor, &format!("{ANSWER_PREFIX}{answer}"))
+        .await?;
+    let updates = TaskUpdates {
+        status: Some("ready"),
+        ..Default::default()
+    };
+    db.update_task(task_id, updates, actor).await?;
+    db.clear_assignee(task_id, actor).await?;
+    Ok(())
+}
+
+/// Whether the task's latest status change took it from `needs_info` back to
+/// `ready`: its next agent picks up the worktree the blocked one left.
+pub fn resumes_after_answer(events: &[Event]) -> bool {
+    events
+        .iter()
+        .rev()
+        .find(|event| event.action == "updated" && event.field.as_deref() == Some("status"))
+        .is_some_and(|event| {
+            event.old_value.as_deref() == Some("needs_info")
+                && event.new_value.as_deref() == Some("ready")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 60 * 60;
+    const NOW: u64 = 1_773_273_600;
+
+    fn candidate(id: &str, priority: i64, age_hours: u64) -> DispatchCandidate {
+        DispatchCandidate {
+            task_id: id.to_string(),
+            priority,
+            created_at: Some(NOW - age_hours * HOUR),
+        }
+    }
+
+    fn ranked(mut candidates: Vec<DispatchCandidate>, policy: DispatchPolicy) -> Vec<String> {
+        rank_candidates(&mut candidates, policy, NOW);
+        candidates.into_iter().map(|c| c.task_id).collect()
+    }
+
+    #[test]
+    fn priority_policy_puts_hotfix_before_older_refactor() {
+        let order = ranked(
+            vec![candidate("refactor", 1, 1), candidate("hotfix", 3, 0)],
+            DispatchPolicy::Priority,
+        );
+
+        assert_eq!(order, ["hotfix", "refactor"]);
+    }
+
+    #[test]
+    fn priority_policy_breaks_ties_by_age() {
+        let order = ranked(
+            vec![candidate("newer", 2, 0), candidate("older", 2, 1)],
+            DispatchPolicy::Priority,
+        );
+
+        assert_eq!(order, ["older", "newer"]);
+    }
+
+    #[test]
+    fn long_waiting_low_priority_task_is_not_starved() {
+        let order = ranked(
+            vec![candidate("hotfix", 3, 0), candidate("ancient", 0, 8)],
+            DispatchPolicy::Priority,
+        );
+
+        assert_eq!(order, ["ancient", "hotfix"]);
+    }
+
+    #[test]
+    fn fifo_policy_ignores_priority() {
+        let mut unknown_age = candidate("unknown", 3, 0);
+        unknown_age.created_at = None;
+        let order = ranked(
+            vec![
+                candidate("hotfix", 3, 0),
+                unknown_age,
+                candidate("old", 0, 5),
+            ],
+            DispatchPolicy::Fifo,
+        );
+
+        assert_eq!(order, ["old", "hotfix", "unknown"]);
+    }
+
+    fn status_change(id: i64, actor: &str, from: &str, to: &str) -> Event {
+        Event {
+            id,
+            task_id: "lt-test".to_string(),
+            actor: actor.to_string(),
+            action: "updated".to_string(),
+            field: Some("status".to_string()),
+            old_value: Some(from.to_string()),
+            new_value: Some(to.to_string()),
+            timestamp: "2026-03-12T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn only_answered_tasks_resume_their_worktree() {
+        let answered = vec![
+            status_change(1, "runtime", "in_progress", "needs_info"),
+            status_change(2, "user", "needs_info", "ready"),
+        ];
+        assert!(resumes_after_answer(&answered));
+
+        let rejected = vec![status_change(1, "reviewer", "in_review", "ready")];
+        assert!(!resumes_after_answer(&rejected));
+        assert!(!resumes_after_answer(&[]));
+    }
+}
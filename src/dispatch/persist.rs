@@ -0,0 +1,131 @@
+//! Sidecar file (`dispatch_state.json`, next to tasks.db) holding the
+//! dispatcher's assignments, so a restart can restore claim times, idle
+//! clocks and attempt numbers instead of guessing from `task.assignee`.
+//!
+//! Times are stored as Unix seconds. `clean_shutdown` is written as false on
+//! every save while running and true by the runtime's shutdown, so a file
+//! still marked false after a restart means the daemon died mid-task.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Minimum time between saves caused only by heartbeats.
+pub(super) const HEARTBEAT_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DispatchState {
+    /// When the file was written.
+    pub saved_at: u64,
+    /// Whether the runtime stopped through its shutdown path.
+    pub clean_shutdown: bool,
+    pub assignments: Vec<SavedAssignment>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SavedAssignment {
+    pub task_id: String,
+    pub agent_name: String,
+    pub claimed_at: u64,
+    pub last_heartbeat: u64,
+    pub attempt: u32,
+    /// Session store key holding the agent's conversation.
+    pub session_key: String,
+    /// Backend that wrote the session (`BackendKind::name`).
+    #[serde(default)]
+    pub backend: String,
+}
+
+impl DispatchState {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let state = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(state))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn assignment(&self, task_id: &str) -> Option<&SavedAssignment> {
+        self.assignments.iter().find(|a| a.task_id == task_id)
+    }
+}
+
+impl SavedAssignment {
+    /// Idle time when the state was saved. Downtime does not count as idle.
+    pub fn idle_at_save(&self, saved_at: u64) -> Duration {
+        Duration::from_secs(saved_at.saturating_sub(self.last_heartbeat))
+    }
+}
+
+/// Unix seconds for an `Instant` in the past.
+pub(super) fn unix_at(instant: Instant, now_unix: u64) -> u64 {
+    now_unix.saturating_sub(instant.elapsed().as_secs())
+}
+
+/// The `Instant` that lies `ago` before now (or now, if that underflows).
+pub(super) fn instant_ago(ago: Duration) -> Instant {
+    let now = Instant::now();
+    now.checked_sub(ago).unwrap_or(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assignment(last_heartbeat: u64) -> SavedAssignment {
+        SavedAssignment {
+            task_id: "lt-1".to_string(),
+            agent_name: "task-lt-1".to_string(),
+            claimed_at: 1_000,
+            last_heartbeat,
+            attempt: 2,
+            session_key: "task-lt-1".to_string(),
+            backend: "claude".to_string(),
+        }
+    }
+
+    #[test]
+    fn idle_excludes_downtime() {
+        let saved = assignment(1_900);
+
+        assert_eq!(saved.idle_at_save(2_000), Duration::from_secs(100));
+        assert_eq!(saved.idle_at_save(1_800), Duration::ZERO);
+    }
+
+    #[test]
+    fn state_round_trips_through_file() {
+        let dir = std::env::temp_dir().join(format!("dispatch-state-{}", std::process::id()));
+        let path = dir.join("dispatch_state.json");
+        assert_eq!(DispatchState::load(&path).unwrap(), None);
+
+        let state = DispatchState {
+            saved_at: 2_000,
+            clean_shutdown: true,
+            assignments: vec![assignment(1_900)],
+        };
+        state.save(&path).unwrap();
+
+        let loaded = DispatchState::load(&path).unwrap().unwrap();
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.assignment("lt-1").map(|a| a.attempt), Some(2));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
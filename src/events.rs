@@ -0,0 +1,250 @@
+//! Structured orchestrator events for `agent-orchestrator watch`.
+//!
+//! Runtimes publish into a shared `EventHub`, which keeps a bounded ring of
+//! recent events with increasing sequence numbers. The control socket is
+//! request/response only, so subscribers long-poll: they send the last
+//! sequence they saw and get everything newer, waiting if there is nothing yet.
+//! A subscriber whose cursor has already fallen out of the ring is told how
+//! many events it missed instead of silently skipping them.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::runtime_support as support;
+
+/// Events kept for subscribers that fall behind.
+const EVENT_BUFFER: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    TaskClaimed,
+    AgentSpawned,
+    Heartbeat,
+    TaskCompleted,
+    TaskBlocked,
+    CheckResult,
+    ReviewVerdict,
+    MergeResult,
+    Timeout,
+    TaskFailed,
+    TaskCancelled,
+    AgentCrashed,
+}
+
+impl EventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::TaskClaimed => "task_claimed",
+            EventKind::AgentSpawned => "agent_spawned",
+            EventKind::Heartbeat => "heartbeat",
+            EventKind::TaskCompleted => "task_completed",
+            EventKind::TaskBlocked => "task_blocked",
+            EventKind::CheckResult => "check_result",
+            EventKind::ReviewVerdict => "review_verdict",
+            EventKind::MergeResult => "merge_result",
+            EventKind::Timeout => "timeout",
+            EventKind::TaskFailed => "task_failed",
+            EventKind::TaskCancelled => "task_cancelled",
+            EventKind::AgentCrashed => "agent_crashed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrchestratorEvent {
+    pub seq: u64,
+    /// Unix seconds.
+    pub timestamp: u64,
+    pub project: String,
+    pub kind: EventKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// Short human-readable detail (verdict, merge outcome, failure reason).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// What a subscriber gets back from one long-poll.
+#[derive(Clone, Debug, Default)]
+pub struct EventBatch {
+    pub events: Vec<OrchestratorEvent>,
+    /// Pass as `after` on the next request.
+    pub cursor: u64,
+    /// Events (of any project) dropped from the ring before they were seen.
+    pub missed: u64,
+}
+
+/// Shared publisher for orchestrator events.
+pub struct EventHub {
+    buffer: Mutex<VecDeque<OrchestratorEvent>>,
+    latest: watch::Sender<u64>,
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        Self {
+            buffer: Mutex::new(VecDeque::with_capacity(EVENT_BUFFER)),
+            latest: watch::channel(0).0,
+        }
+    }
+
+    /// Record an event and wake subscribers.
+    pub fn publish(
+        &self,
+        project: &str,
+        kind: EventKind,
+        task_id: Option<&str>,
+        agent: Option<&str>,
+        detail: Option<String>,
+    ) {
+        let mut buffer = self.buffer.lock().unwrap();
+        let seq = *self.latest.borrow() + 1;
+        if buffer.len() == EVENT_BUFFER {
+            buffer.pop_front();
+        }
+        buffer.push_back(OrchestratorEvent {
+            seq,
+            timestamp: support::unix_now(),
+            project: project.to_string(),
+            kind,
+            task_id: task_id.map(str::to_string),
+            agent: agent.map(str::to_string),
+            detail,
+        });
+        self.latest.send_replace(seq);
+    }
+
+    /// Sequence number of the newest event (0 if none yet).
+    pub fn cursor(&self) -> u64 {
+        *self.latest.borrow()
+    }
+
+    /// Events after `after` (optionally for one project), waiting up to
+    /// `wait` for one to arrive. Returns at once if events were missed.
+    pub async fn wait_after(
+        &self,
+        after: Option<u64>,
+        project: Option<&str>,
+        wait: Duration,
+    ) -> EventBatch {
+        let mut rx = self.latest.subscribe();
+        let after = after.unwrap_or_else(|| *rx.borrow_and_update());
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            let batch = self.events_after(after, project);
+            if !batch.events.is_empty() || batch.missed > 0 {
+                return batch;
+            }
+            let changed = tokio::time::timeout_at(deadline, rx.changed()).await;
+            if !matches!(changed, Ok(Ok(()))) {
+                return batch;
+            }
+        }
+    }
+
+    fn events_after(&self, after: u64, project: Option<&str>) -> EventBatch {
+        let buffer = self.buffer.lock().unwrap();
+        let cursor = buffer.back().map_or(after, |event| event.seq.max(after));
+        let missed = buffer
+            .front()
+            .map_or(0, |event| event.seq.saturating_sub(after + 1));
+        let events = buffer
+            .iter()
+            .filter(|event| event.seq > after)
+            .filter(|event| project.is_none_or(|p| event.project == p))
+            .cloned()
+            .collect();
+        EventBatch {
+            events,
+            cursor,
+            missed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn wait_after_returns_newer_events_for_project() {
+        let hub = EventHub::new();
+        hub.publish("a", EventKind::TaskClaimed, Some("lt-1"), None, None);
+        hub.publish("b", EventKind::TaskClaimed, Some("lt-2"), None, None);
+        hub.publish("a", EventKind::TaskCompleted, Some("lt-1"), None, None);
+
+        let batch = hub
+            .wait_after(Some(1), Some("a"), Duration::from_millis(10))
+            .await;
+
+        assert_eq!(batch.cursor, 3);
+        assert_eq!(batch.missed, 0);
+        assert_eq!(batch.events.len(), 1);
+        assert_eq!(batch.events[0].kind, EventKind::TaskCompleted);
+    }
+
+    #[tokio::test]
+    async fn wait_after_without_cursor_waits_for_new_events() {
+        let hub = Arc::new(EventHub::new());
+        hub.publish("a", EventKind::TaskClaimed, Some("lt-old"), None, None);
+
+        let publisher = hub.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            publisher.publish("a", EventKind::Timeout, Some("lt-new"), None, None);
+        });
+        let batch = hub.wait_after(None, None, Duration::from_secs(5)).await;
+
+        assert_eq!(batch.cursor, 2);
+        assert_eq!(batch.events.len(), 1);
+        assert_eq!(batch.events[0].task_id.as_deref(), Some("lt-new"));
+    }
+
+    #[tokio::test]
+    async fn wait_after_times_out_with_unchanged_cursor() {
+        let hub = EventHub::new();
+        hub.publish("a", EventKind::Heartbeat, None, Some("task-lt-1"), None);
+
+        let batch = hub
+            .wait_after(Some(1), Some("a"), Duration::from_millis(10))
+            .await;
+
+        assert!(batch.events.is_empty());
+        assert_eq!(batch.cursor, 1);
+    }
+
+    #[tokio::test]
+    async fn wait_after_reports_events_dropped_from_the_ring() {
+        let hub = EventHub::new();
+        for _ in 0..EVENT_BUFFER + 5 {
+            hub.publish("b", EventKind::Heartbeat, None, Some("task-lt-2"), None);
+        }
+
+        let batch = hub
+            .wait_after(Some(2), Some("a"), Duration::from_secs(5))
+            .await;
+
+        assert_eq!(batch.missed, 3);
+        assert!(batch.events.is_empty());
+        assert_eq!(batch.cursor, EVENT_BUFFER as u64 + 5);
+
+        let batch = hub
+            .wait_after(Some(batch.cursor), None, Duration::from_millis(10))
+            .await;
+        assert_eq!(batch.missed, 0);
+    }
+}
@@ -0,0 +1,313 @@
+//! Cleanup of task branches and worktrees left behind by finished tasks.
+//!
+//! The merge path deletes a task's branch and worktree once its work lands,
+//! but failed, cancelled and aborted tasks (and crashes mid-merge) leave
+//! `agent/task-*` branches and `.worktrees/task-*` directories behind.
+//! Only tasks in a terminal status, or no longer in the DB, are touched.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use llm_tasks::db::Database;
+
+use crate::config::WorktreeSetup;
+use crate::runtime::CANCELLED_STATUS;
+use crate::worktree::{self, WorktreeConfig};
+
+/// Unmerged branches of failed or vanished tasks are moved here instead of
+/// being deleted: `{ARCHIVE_REF_PREFIX}/agent/task-<id>`.
+pub const ARCHIVE_REF_PREFIX: &str = "refs/orchestrator/archive";
+
+const TASK_BRANCH_PREFIX: &str = "agent/task-";
+
+/// What a GC pass removed, or would remove in dry-run mode.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub dry_run: bool,
+    pub removed_worktrees: Vec<PathBuf>,
+    /// Branches already merged into the target branch.
+    pub deleted_branches: Vec<String>,
+    /// Unmerged branches, as (branch, archive ref).
+    pub archived_branches: Vec<(String, String)>,
+}
+
+impl GcReport {
+    pub fn is_empty(&self) -> bool {
+        self.removed_worktrees.is_empty()
+            && self.deleted_branches.is_empty()
+            && self.archived_branches.is_empty()
+    }
+
+    /// One line per removed item, prefixed with "would" in dry-run mode.
+    pub fn lines(&self) -> Vec<String> {
+        let verb = |done: &str, planned: &str| {
+            if self.dry_run {
+                planned.to_string()
+            } else {
+                done.to_string()
+            }
+        };
+        let mut lines = Vec::new();
+        for path in &self.removed_worktrees {
+            lines.push(format!(
+                "{} worktree {}",
+                verb("removed", "would remove"),
+                path.display()
+            ));
+        }
+        for branch in &self.deleted_branches {
+            lines.push(format!(
+                "{} merged branch {}",
+                verb("deleted", "would delete"),
+                branch
+            ));
+        }
+        for (branch, archive) in &self.archived_branches {
+            lines.push(format!(
+                "{} unmerged branch {} to {}",
+                verb("archived", "would archive"),
+                branch,
+                archive
+            ));
+        }
+        lines
+    }
+}
+
+/// Whether a task in `status` is finished with its branch and worktree.
+/// `None` means the task is not in the DB.
+fn is_finished(status: Option<&str>) -> bool {
+    matches!(status, None | Some("done" | "failed" | CANCELLED_STATUS))
+}
+
+/// Remove worktrees and branches of finished tasks in `project_dir`, leaving
+/// out tasks in `busy` (assigned or mid-merge). Branches count as merged
+/// when they are contained in the task's target branch, or `default_branch`.
+/// Items that can't be removed are logged and left for the next pass.
+/// The git work runs on the blocking pool once the task list is fetched.
+pub async fn collect_garbage(
+    db: &Database,
+    project_dir: &Path,
+    default_branch: &str,
+    setup: &WorktreeSetup,
+    busy: &HashSet<String>,
+    dry_run: bool,
+) -> Result<GcReport> {
+    let tasks: HashMap<String, (String, Option<String>)> = db
+        .list_tasks(None, None)
+        .await
+        .context("failed to list tasks")?
+        .into_iter()
+        .map(|task| (task.id, (task.status, task.target_branch)))
+        .collect();
+    let (project_dir, default_branch) = (project_dir.to_path_buf(), default_branch.to_string());
+    let (setup, busy) = (setup.clone(), busy.clone());
+    tokio::task::spawn_blocking(move || {
+        collect_finished(
+            &tasks,
+            &project_dir,
+            &default_branch,
+            &setup,
+            &busy,
+            dry_run,
+        )
+    })
+    .await
+    .context("GC pass panicked")?
+}
+
+/// The blocking part of `collect_garbage`, given each task's status and
+/// target branch by id.
+fn collect_finished(
+    tasks: &HashMap<String, (String, Option<String>)>,
+    project_dir: &Path,
+    default_branch: &str,
+    setup: &WorktreeSetup,
+    busy: &HashSet<String>,
+    dry_run: bool,
+) -> Result<GcReport> {
+    let status_of = |task_id: &str| tasks.get(task_id).map(|(status, _)| status.as_str());
+    let collectable = |task_id: &str| !busy.contains(task_id) && is_finished(status_of(task_id));
+    let target_of = |task_id: &str| {
+        tasks
+            .get(task_id)
+            .and_then(|(_, target)| target.as_deref())
+            .unwrap_or(default_branch)
+    };
+
+    let mut report = GcReport {
+        dry_run,
+        ..Default::default()
+    };
+    if !dry_run {
+        git(project_dir, &["worktree", "prune"])?;
+    }
+
+    for (agent_name, path) in task_worktree_dirs(project_dir) {
+        let task_id = &agent_name["task-".len()..];
+        if !collectable(task_id) {
+            continue;
+        }
+        if !dry_run
+            && let Err(e) =
+                remove_task_worktree(project_dir, &agent_name, &path, target_of(task_id), setup)
+        {
+            tracing::warn!("GC: keeping worktree {}: {:#}", path.display(), e);
+            continue;
+        }
+        report.removed_worktrees.push(path);
+    }
+
+    for branch in task_branches(project_dir)? {
+        let task_id = &branch[TASK_BRANCH_PREFIX.len()..];
+        if !collectable(task_id) {
+            continue;
+        }
+        if is_merged(project_dir, &branch, target_of(task_id)) {
+            if !dry_run && let Err(e) = git(project_dir, &["branch", "-D", &branch]) {
+                tracing::warn!("GC: keeping branch {}: {:#}", branch, e);
+                continue;
+            }
+            report.deleted_branches.push(branch);
+        } else if status_of(task_id) != Some("done") {
+            let archive = format!("{}/{}", ARCHIVE_REF_PREFIX, branch);
+            if !dry_run && let Err(e) = archive_branch(project_dir, &branch, &archive) {
+                tracing::warn!("GC: keeping branch {}: {:#}", branch, e);
+                continue;
+            }
+            report.archived_branches.push((branch, archive));
+        }
+    }
+    Ok(report)
+}
+
+/// `.worktrees/task-*` directories as (agent name, path). Symlinked
+/// aliases in `.worktrees` are skipped.
+fn task_worktree_dirs(project_dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(project_dir.join(".worktrees")) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            name.starts_with("task-").then(|| (name, entry.path()))
+        })
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Remove a registered worktree through the worktree module, which saves
+/// uncommitted changes first. Plain directories git no longer tracks are
+/// deleted outright.
+fn remove_task_worktree(
+    project_dir: &Path,
+    agent_name: &str,
+    path: &Path,
+    target_branch: &str,
+    setup: &WorktreeSetup,
+) -> Result<()> {
+    if path.join(".git").exists() {
+        let cfg = WorktreeConfig {
+            project_dir: project_dir.to_path_buf(),
+            agent_name: agent_name.to_string(),
+            target_branch: target_branch.to_string(),
+        };
+        worktree::remove_worktree(&cfg, setup)?;
+    }
+    if path.exists() {
+        std::fs::remove_dir_all(path)
+            .with_context(|| format!("failed to remove {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn task_branches(project_dir: &Path) -> Result<Vec<String>> {
+    let output = git(
+        project_dir,
+        &[
+            "for-each-ref",
+            "--format=%(refname:short)",
+            &format!("refs/heads/{}*", TASK_BRANCH_PREFIX),
+        ],
+    )?;
+    Ok(output
+        .lines()
+        .filter(|b| b.starts_with(TASK_BRANCH_PREFIX))
+        .map(str::to_string)
+        .collect())
+}
+
+fn is_merged(project_dir: &Path, branch: &str, target_branch: &str) -> bool {
+    Command::new("git")
+        .args(["merge-base", "--is-ancestor", branch, target_branch])
+        .current_dir(project_dir)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+fn archive_branch(project_dir: &Path, branch: &str, archive: &str) -> Result<()> {
+    let full = format!("refs/heads/{}", branch);
+    git(project_dir, &["update-ref", archive, &full])?;
+    git(project_dir, &["branch", "-D", branch])?;
+    Ok(())
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_terminal_or_missing_tasks_are_collected() {
+        assert!(is_finished(None));
+        assert!(is_finished(Some("done")));
+        assert!(is_finished(Some("failed")));
+        assert!(is_finished(Some(CANCELLED_STATUS)));
+        assert!(!is_finished(Some("in_progress")));
+        assert!(!is_finished(Some("ready")));
+        assert!(!is_finished(Some("merge_conflict")));
+    }
+
+    #[test]
+    fn dry_run_report_says_would() {
+        let report = GcReport {
+            dry_run: true,
+            removed_worktrees: vec![PathBuf::from("/p/.worktrees/task-lt-1")],
+            deleted_branches: vec!["agent/task-lt-1".to_string()],
+            archived_branches: vec![(
+                "agent/task-lt-2".to_string(),
+                "refs/orchestrator/archive/agent/task-lt-2".to_string(),
+            )],
+        };
+        assert_eq!(
+            report.lines(),
+            vec![
+                "would remove worktree /p/.worktrees/task-lt-1",
+                "would delete merged branch agent/task-lt-1",
+                "would archive unmerged branch agent/task-lt-2 to refs/orchestrator/archive/agent/task-lt-2",
+            ]
+        );
+    }
+}
@@ -13,6 +13,9 @@ pub mod control;
 pub mod daemon;
 #[cfg_attr(coverage_nightly, coverage(off))]
 pub mod dispatch;
+pub mod events;
+#[cfg_attr(coverage_nightly, coverage(off))]
+pub mod gc;
 #[cfg_attr(coverage_nightly, coverage(off))]
 pub mod mcp;
 #[cfg_attr(coverage_nightly, coverage(off))]
@@ -22,9 +25,12 @@ pub mod relay;
 #[cfg_attr(coverage_nightly, coverage(off))]
 pub mod resume;
 #[cfg_attr(coverage_nightly, coverage(off))]
+pub mod reviewer;
+#[cfg_attr(coverage_nightly, coverage(off))]
 pub mod runtime;
 #[cfg_attr(coverage_nightly, coverage(off))]
 pub mod runtime_support;
+pub mod state;
 #[cfg_attr(coverage_nightly, coverage(off))]
 pub mod task_tools;
 pub mod types;
@@ -31,6 +31,14 @@ async fn dispatch(args: &[String]) -> Result<()> {
         "mcp-tasks" => cmd_mcp_tasks(args).await,
         "status" => cmd_status(args),
         "scale" => cmd_scale(args),
+        "cancel" => cmd_cancel(args),
+        "answer" => cmd_answer(args).await,
+        "pause" => cmd_pause(args, true),
+        "resume" => cmd_pause(args, false),
+        "drain" => cmd_drain(),
+        "watch" => cmd_watch(args),
+        "wip" => cmd_wip(args),
+        "gc" => cmd_gc(args).await,
         _ => {
             print_usage();
             Ok(())
@@ -80,30 +88,11 @@ fn read_backend_table(path: &std::path::Path) -> Option<toml::Table> {
 }
 
 fn parse_backend_kind(table: &toml::Table) -> BackendKind {
-    match table_str(table, "backend").unwrap_or("claude") {
-        "codex" => codex_backend(table),
-        "openrouter" => openrouter_backend(table),
-        _ => BackendKind::Claude,
-    }
-}
-
-fn codex_backend(table: &toml::Table) -> BackendKind {
-    let model = table_str(table, "model").unwrap_or("gpt-5.4");
-    BackendKind::Codex {
-        model: model.to_string(),
-    }
-}
-
-fn openrouter_backend(table: &toml::Table) -> BackendKind {
-    let model = table_str(table, "model").unwrap_or("anthropic/claude-sonnet-4");
-    let api_key = table_str(table, "api_key")
-        .map(str::to_string)
-        .or_else(|| std::env::var("OPENROUTER_API_KEY").ok())
-        .unwrap_or_default();
-    BackendKind::OpenRouter {
-        model: model.to_string(),
-        api_key,
-    }
+    BackendKind::from_name(
+        table_str(table, "backend").unwrap_or("claude"),
+        table_str(table, "model"),
+        table_str(table, "api_key").map(str::to_string),
+    )
 }
 
 fn table_str<'a>(table: &'a toml::Table, key: &str) -> Option<&'a str> {
@@ -174,6 +163,18 @@ COMMANDS:
     notify --project <name> <task-id>           Notify runtime about a new task
     status --project <name>                     Show running agents for a project
     scale <max>                                  Set global max concurrent task agents (1-20)
+    scale --project <name> <max|none> [--priority <n>]
+                                                Set a project's max agents and fair-share weight
+    watch [--project <name>] [--json]           Stream orchestrator events (table or JSON lines)
+    pause --project <name>                      Stop starting new agents for a project
+    resume --project <name>                     Resume dispatching for a paused project
+    drain                                       Finish in-flight agents, then stop the daemon
+    cancel --project <name> <task-id> [--requeue]
+                                                Stop a task's agent; requeue it or mark it cancelled
+    answer --project <name> <task-id> <text>    Answer a needs_info task's question and requeue it
+    gc --project <name> [--dry-run]             Remove branches and worktrees of finished tasks
+    wip list --project <name>                   List saved snapshots of uncommitted worktree changes
+    wip restore --project <name> <ref>          Re-apply a snapshot to its worktree, or branch it
     mcp-serve --agent <name> --socket <path>    Run MCP stdio server for an agent
     mcp-tasks [--project <name>]                Task DB MCP for Claude Code (uses CLAUDE_CODE_TASK_LIST_ID)
 
@@ -181,6 +182,12 @@ EXAMPLES:
     agent-orchestrator daemon
     agent-orchestrator send --project my-project runtime "check status"
     agent-orchestrator scale 5
+    agent-orchestrator scale --project my-project 2 --priority 3
+    agent-orchestrator watch --project my-project --json | jq .
+    agent-orchestrator cancel --project my-project lt-abc123 --requeue
+    agent-orchestrator answer --project my-project lt-abc123 "Use the v2 API"
+    agent-orchestrator gc --project my-project --dry-run
+    agent-orchestrator wip restore --project my-project task-lt-abc123/1760000000
 "#
     );
 }
@@ -241,17 +248,33 @@ fn cmd_status(args: &[String]) -> Result<()> {
     let request = control::ControlRequest::Status { project };
     let response: control::ControlResponse = peercred_ipc::Client::call(&socket, &request)?;
     match response {
-        control::ControlResponse::Status { agents, project } => {
+        control::ControlResponse::Status {
+            agents,
+            project,
+            task_counts,
+            slots,
+            paused,
+            draining,
+        } => {
             let out: Vec<serde_json::Value> = agents
-                .iter()
-                .map(|a| {
-                    let task_id = a.name.strip_prefix("task-").map(String::from);
-                    serde_json::json!({ "name": a.name, "role": a.role, "task_id": task_id })
+                .into_iter()
+                .map(|mut a| {
+                    if a.task_id.is_none() {
+                        a.task_id = a.name.strip_prefix("task-").map(String::from);
+                    }
+                    serde_json::to_value(a).unwrap_or_default()
                 })
                 .collect();
             println!(
                 "{}",
-                serde_json::json!({ "project": project, "agents": out })
+                serde_json::json!({
+                    "project": project,
+                    "agents": out,
+                    "task_counts": task_counts,
+                    "slots": slots,
+                    "paused": paused,
+                    "draining": draining,
+                })
             );
         }
         control::ControlResponse::Error { message } => bail!("Error: {message}"),
@@ -261,6 +284,9 @@ fn cmd_status(args: &[String]) -> Result<()> {
 }
 
 fn cmd_scale(args: &[String]) -> Result<()> {
+    if let Some(project) = extract_named_arg(args, "--project") {
+        return cmd_scale_project(args, project);
+    }
     let max: u8 = args
         .get(2)
         .ok_or_else(|| anyhow::anyhow!("Usage: agent-orchestrator scale <max>"))?
@@ -278,6 +304,291 @@ fn cmd_scale(args: &[String]) -> Result<()> {
     Ok(())
 }
 
+fn cmd_scale_project(args: &[String], project: String) -> Result<()> {
+    let usage = "Usage: agent-orchestrator scale --project <name> <max|none> [--priority <n>]";
+    let priority = extract_named_arg(args, "--priority")
+        .map(|p| p.parse::<u32>())
+        .transpose()
+        .map_err(|_| anyhow::anyhow!("priority must be a positive number"))?;
+    let max_arg = positional_args(args, &["--project", "--priority"])
+        .into_iter()
+        .nth(1)
+        .ok_or_else(|| anyhow::anyhow!(usage))?;
+    let max = match max_arg.as_str() {
+        "none" => None,
+        value => Some(
+            value
+                .parse::<u8>()
+                .map_err(|_| anyhow::anyhow!("max must be a number 1-20 or 'none'"))?
+                .clamp(1, 20),
+        ),
+    };
+    let socket = control::control_socket_path();
+    let request = control::ControlRequest::SetProjectConcurrency {
+        project: project.clone(),
+        max,
+        priority,
+    };
+    let response: control::ControlResponse = peercred_ipc::Client::call(&socket, &request)?;
+    match response {
+        control::ControlResponse::Ok => match max {
+            Some(max) => println!("Max concurrency for {project} set to {max}"),
+            None => println!("Removed max concurrency for {project}"),
+        },
+        control::ControlResponse::Error { message } => bail!("Error: {message}"),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn cmd_pause(args: &[String], paused: bool) -> Result<()> {
+    let command = if paused { "pause" } else { "resume" };
+    let project = extract_named_arg(args, "--project")
+        .ok_or_else(|| anyhow::anyhow!("--project required for {command}"))?;
+    let socket = control::control_socket_path();
+    let request = if paused {
+        control::ControlRequest::Pause {
+            project: project.clone(),
+        }
+    } else {
+        control::ControlRequest::Resume {
+            project: project.clone(),
+        }
+    };
+    let response: control::ControlResponse = peercred_ipc::Client::call(&socket, &request)?;
+    match response {
+        control::ControlResponse::Ok if paused => println!("Dispatching paused for {project}"),
+        control::ControlResponse::Ok => println!("Dispatching resumed for {project}"),
+        control::ControlResponse::Error { message } => bail!("Error: {message}"),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn cmd_drain() -> Result<()> {
+    let socket = control::control_socket_path();
+    let request = control::ControlRequest::Drain;
+    let response: control::ControlResponse = peercred_ipc::Client::call(&socket, &request)?;
+    match response {
+        control::ControlResponse::Ok => {
+            println!("Draining: the daemon exits once in-flight agents finish")
+        }
+        control::ControlResponse::Error { message } => bail!("Error: {message}"),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn cmd_cancel(args: &[String]) -> Result<()> {
+    let project = extract_named_arg(args, "--project")
+        .ok_or_else(|| anyhow::anyhow!("--project required for cancel"))?;
+    let requeue = args.iter().any(|a| a == "--requeue");
+    let task_id = positional_args(args, &["--project"])
+        .into_iter()
+        .filter(|a| a != "--requeue")
+        .nth(1)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Usage: agent-orchestrator cancel --project <name> <task-id> [--requeue]"
+            )
+        })?;
+    let socket = control::control_socket_path();
+    let request = control::ControlRequest::CancelTask {
+        project,
+        task_id: task_id.clone(),
+        requeue,
+    };
+    let response: control::ControlResponse = peercred_ipc::Client::call(&socket, &request)?;
+    match response {
+        control::ControlResponse::Ok if requeue => println!("Cancelled {task_id}, requeued"),
+        control::ControlResponse::Ok => println!("Cancelled {task_id}"),
+        control::ControlResponse::Error { message } => bail!("Error: {message}"),
+        _ => {}
+    }
+    Ok(())
+}
+
+async fn cmd_answer(args: &[String]) -> Result<()> {
+    let project = extract_named_arg(args, "--project")
+        .ok_or_else(|| anyhow::anyhow!("--project required for answer"))?;
+    let positional = positional_args(args, &["--project"]);
+    if positional.len() < 3 {
+        bail!("Usage: agent-orchestrator answer --project <name> <task-id> <text>");
+    }
+    let task_id = &positional[1];
+    let answer = positional[2..].join(" ");
+    let db_path = agent_orchestrator::daemon::db_path_for_project(&project);
+    let db = llm_tasks::db::Database::open(&db_path).await?;
+    agent_orchestrator::dispatch::record_answer(&db, task_id, &answer, "user").await?;
+    println!("Answered {task_id}, back to ready");
+
+    // Dispatch right away if the daemon is running; otherwise on its next poll.
+    let socket = control::control_socket_path();
+    let request = control::ControlRequest::NotifyTaskReady {
+        project,
+        task_id: task_id.clone(),
+    };
+    let response: Result<control::ControlResponse, _> =
+        peercred_ipc::Client::call(&socket, &request);
+    if let Ok(control::ControlResponse::Error { message }) = response {
+        eprintln!("Runtime not notified: {message}");
+    }
+    Ok(())
+}
+
+async fn cmd_gc(args: &[String]) -> Result<()> {
+    let project = extract_named_arg(args, "--project")
+        .ok_or_else(|| anyhow::anyhow!("--project required for gc"))?;
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+    let projects = agent_orchestrator::config::load_config()?;
+    let cfg = projects
+        .get(&project)
+        .ok_or_else(|| anyhow::anyhow!("Unknown project {project}"))?;
+    let default_branch = cfg
+        .default_branch
+        .as_deref()
+        .unwrap_or(agent_orchestrator::config::DEFAULT_BRANCH);
+    let db_path = agent_orchestrator::daemon::db_path_for_project(&project);
+    let db = llm_tasks::db::Database::open(&db_path).await?;
+    let report = agent_orchestrator::gc::collect_garbage(
+        &db,
+        std::path::Path::new(&cfg.dir),
+        default_branch,
+        &cfg.worktree,
+        &Default::default(),
+        dry_run,
+    )
+    .await?;
+    if report.is_empty() {
+        println!("Nothing to clean up for {project}");
+    }
+    for line in report.lines() {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn cmd_wip(args: &[String]) -> Result<()> {
+    use agent_orchestrator::worktree::{self, WipRestore};
+    let usage = "Usage: agent-orchestrator wip <list|restore> --project <name> [<ref>]";
+    let project = extract_named_arg(args, "--project")
+        .ok_or_else(|| anyhow::anyhow!("--project required for wip"))?;
+    let projects = agent_orchestrator::config::load_config()?;
+    let dir = projects
+        .get(&project)
+        .map(|cfg| PathBuf::from(&cfg.dir))
+        .ok_or_else(|| anyhow::anyhow!("Unknown project {project}"))?;
+    let positional = positional_args(args, &["--project"]);
+    match positional.get(1).map(String::as_str) {
+        Some("list") => {
+            let refs = worktree::list_wip_refs(&dir)?;
+            if refs.is_empty() {
+                println!("No saved WIP for {project}");
+            }
+            for wip in refs {
+                let short = wip
+                    .name
+                    .strip_prefix(worktree::WIP_REF_PREFIX)
+                    .map(|n| n.trim_start_matches('/'))
+                    .unwrap_or(&wip.name);
+                println!(
+                    "{}\t{}\t{}",
+                    short,
+                    &wip.commit[..12.min(wip.commit.len())],
+                    wip.subject
+                );
+            }
+        }
+        Some("restore") => {
+            let name = positional.get(2).ok_or_else(|| anyhow::anyhow!(usage))?;
+            match worktree::restore_wip(&dir, name)? {
+                WipRestore::Applied(path) => {
+                    println!("Restored {name} into {}", path.display())
+                }
+                WipRestore::Branch(branch) => println!("Restored {name} as branch {branch}"),
+            }
+        }
+        _ => bail!(usage),
+    }
+    Ok(())
+}
+
+/// Arguments after the command name, skipping the given flags and their values.
+fn positional_args(args: &[String], flags: &[&str]) -> Vec<String> {
+    let mut positional = Vec::new();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if flags.contains(&arg.as_str()) {
+            iter.next();
+            continue;
+        }
+        positional.push(arg.clone());
+    }
+    positional
+}
+
+fn cmd_watch(args: &[String]) -> Result<()> {
+    use control::{ControlRequest, ControlResponse};
+    let project = extract_named_arg(args, "--project");
+    let json = args.iter().any(|a| a == "--json");
+    let socket = control::control_socket_path();
+    let timeout = control::SUBSCRIBE_WAIT + std::time::Duration::from_secs(10);
+    let mut after = None;
+    if !json {
+        println!(
+            "{:<8}  {:<16}  {:<14}  {:<14}  {:<20}  DETAIL",
+            "TIME", "PROJECT", "EVENT", "TASK", "AGENT"
+        );
+    }
+    loop {
+        let request = ControlRequest::Subscribe {
+            project: project.clone(),
+            after,
+        };
+        let response: ControlResponse =
+            peercred_ipc::Client::call_timeout(&socket, &request, timeout)?;
+        match response {
+            ControlResponse::Events {
+                events,
+                cursor,
+                missed,
+            } => {
+                if missed > 0 {
+                    if json {
+                        println!("{}", serde_json::json!({ "missed": missed }));
+                    } else {
+                        println!("... {missed} events missed (watcher fell behind)");
+                    }
+                }
+                for event in &events {
+                    if json {
+                        println!("{}", serde_json::to_string(event)?);
+                    } else {
+                        print_event_row(event);
+                    }
+                }
+                after = Some(cursor);
+            }
+            ControlResponse::Error { message } => bail!("Error: {message}"),
+            _ => bail!("Unexpected response to subscribe"),
+        }
+    }
+}
+
+fn print_event_row(event: &agent_orchestrator::events::OrchestratorEvent) {
+    let secs = event.timestamp % 86_400;
+    let time = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    println!(
+        "{:<8}  {:<16}  {:<14}  {:<14}  {:<20}  {}",
+        time,
+        event.project,
+        event.kind.as_str(),
+        event.task_id.as_deref().unwrap_or("-"),
+        event.agent.as_deref().unwrap_or("-"),
+        event.detail.as_deref().unwrap_or("").replace('\n', " "),
+    );
+}
+
 fn send_message(project: &str, to: &str, content: &str) -> Result<()> {
     use control::{ControlRequest, ControlResponse};
     let socket = control::control_socket_path();
@@ -33,7 +33,7 @@ struct AddTaskParams {
 
 #[derive(Debug, Deserialize, JsonSchema)]
 struct ListTasksParams {
-    /// Filter by status: pending, ready, in_progress, needs_info, in_review, done, pending_delete
+    /// Filter by status: pending, ready, in_progress, needs_info, in_review, merge_conflict, done, cancelled, pending_delete
     status: Option<String>,
     /// Filter by assignee (e.g. "developer-0")
     assignee: Option<String>,
@@ -91,6 +91,30 @@ struct AddCommentParams {
     content: String,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+struct AnswerTaskParams {
+    /// Task ID of a needs_info task
+    id: String,
+    /// Answer to the agent's question
+    answer: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SetMaxAttemptsParams {
+    /// Task ID
+    id: String,
+    /// How many dispatches this task gets before it is marked failed
+    max_attempts: u32,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CancelTaskParams {
+    /// Task ID
+    id: String,
+    /// Put the task back to ready instead of marking it cancelled
+    requeue: Option<bool>,
+}
+
 #[derive(Debug, Deserialize, JsonSchema)]
 struct SetConcurrencyParams {
     /// Global maximum number of parallel task agents (1-20). Each agent runs in its own git worktree.
@@ -101,6 +125,8 @@ struct SetConcurrencyParams {
 
 struct TasksMcp {
     db: Arc<Database>,
+    /// Where per-task attempt limits are kept (next to the database).
+    attempt_limits: std::path::PathBuf,
     project: String,
     tool_router: ToolRouter<Self>,
 }
@@ -246,6 +272,65 @@ impl TasksMcp {
         }
     }
 
+    #[tool(
+        description = "Answer the question of a needs_info task. The task goes back to ready and its next agent gets the answer and the blocked agent's worktree."
+    )]
+    async fn answer_task(&self, Parameters(p): Parameters<AnswerTaskParams>) -> String {
+        match crate::dispatch::record_answer(&self.db, &p.id, &p.answer, "user").await {
+            Ok(()) => {
+                notify_task_ready(&self.project, &p.id);
+                format!("Answered {}; it is ready for dispatch", p.id)
+            }
+            Err(e) => err(e),
+        }
+    }
+
+    #[tool(
+        description = "Override the project's attempt limit for one task. Kept by the orchestrator; the newest override wins."
+    )]
+    async fn set_max_attempts(&self, Parameters(p): Parameters<SetMaxAttemptsParams>) -> String {
+        if p.max_attempts == 0 {
+            return "Error: max_attempts must be at least 1".to_string();
+        }
+        if let Err(e) = self.db.get_task(&p.id).await {
+            return err(e);
+        }
+        match crate::runtime::set_max_attempts(&self.attempt_limits, &p.id, p.max_attempts) {
+            Ok(()) => format!("Task {} may be attempted {} times", p.id, p.max_attempts),
+            Err(e) => err(e),
+        }
+    }
+
+    #[tool(
+        description = "Stop the agent working on a task. The task is marked cancelled, or put back to ready with requeue=true. Requires a running orchestrator."
+    )]
+    async fn cancel_task(&self, Parameters(p): Parameters<CancelTaskParams>) -> String {
+        let requeue = p.requeue.unwrap_or(false);
+        let socket_path = control::control_socket_path();
+        let req = control::ControlRequest::CancelTask {
+            project: self.project.clone(),
+            task_id: p.id.clone(),
+            requeue,
+        };
+        match tokio::task::spawn_blocking(move || {
+            peercred_ipc::Client::call::<_, control::ControlRequest, control::ControlResponse>(
+                &socket_path,
+                &req,
+            )
+        })
+        .await
+        {
+            Ok(Ok(control::ControlResponse::Ok)) if requeue => {
+                format!("Cancelled {} and requeued it", p.id)
+            }
+            Ok(Ok(control::ControlResponse::Ok)) => format!("Cancelled {}", p.id),
+            Ok(Ok(control::ControlResponse::Error { message })) => format!("Error: {message}"),
+            Ok(Ok(resp)) => format!("Unexpected response: {resp:?}"),
+            Ok(Err(e)) => format!("Error: {e} (is the orchestrator running?)"),
+            Err(e) => format!("Error: {e}"),
+        }
+    }
+
     #[tool(
         description = "Scale the global maximum number of parallel task agents (1-20) across all projects. Requires a running orchestrator."
     )]
@@ -332,6 +417,20 @@ fn notify_runtime(project: &str, task_id: &str) {
     );
 }
 
+/// Notify the running orchestrator that a task is ready for dispatch again.
+/// Silently fails if no orchestrator is running.
+fn notify_task_ready(project: &str, task_id: &str) {
+    let socket_path = control::control_socket_path();
+    let req = control::ControlRequest::NotifyTaskReady {
+        project: project.to_string(),
+        task_id: task_id.to_string(),
+    };
+    let _ = peercred_ipc::Client::call::<_, control::ControlRequest, control::ControlResponse>(
+        &socket_path,
+        &req,
+    );
+}
+
 pub async fn run(db_path: &std::path::Path, project: &str, register_cwd: bool) -> Result<()> {
     if register_cwd && let Ok(cwd) = std::env::current_dir() {
         let cwd = cwd.to_string_lossy().into_owned();
@@ -345,6 +444,7 @@ pub async fn run(db_path: &std::path::Path, project: &str, register_cwd: bool) -
     let db = Database::open(db_path).await?;
     let service = TasksMcp {
         db: Arc::new(db),
+        attempt_limits: crate::runtime::attempt_limits_path(db_path),
         project: project.to_string(),
         tool_router: TasksMcp::tool_router(),
     };
@@ -2,21 +2,43 @@
 //!
 //! On startup, finds tasks that were `in_progress` when the daemon last shut down,
 //! spawns agents with their preserved worktrees (partial work intact), and
-//! re-dispatches with a resume prompt that includes the git diff.
+//! re-dispatches with a resume prompt that includes the branch's commit log,
+//! uncommitted changes and a size-budgeted patch.
+//! Assignments saved by the dispatcher restore each task's claim time, idle
+//! clock and attempt, and tell a crash apart from a clean shutdown. When the
+//! agent's stored session is still usable it is continued with a short
+//! "you were interrupted" message; otherwise the diff-based prompt starts over.
 
 use std::path::{Path, PathBuf};
 use std::process::Command;
-
-use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 use anyhow::Result;
+use llm_sdk::session::SessionStore;
 
 use crate::agent::{AgentConfig, BackendKind};
-use crate::runtime::OrchestratorRuntime;
+use crate::dispatch::{DispatchState, SavedAssignment};
+use crate::events::EventKind;
+use crate::runtime::{OrchestratorRuntime, PostCreate};
 use crate::runtime_support as support;
 use crate::types::{AgentId, AgentRole};
 use crate::worktree::{self, WorktreeConfig};
 
+/// Sessions idle longer than this are not reattached; the task starts over.
+const SESSION_RESUME_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// Patch characters a resume prompt may include before files are summarized.
+const RESUME_PATCH_BUDGET: usize = 12_000;
+/// Cap on the commit log, status and omitted-file list in a resume prompt.
+const RESUME_LIST_MAX_CHARS: usize = 2_000;
+
+/// How a resumed task's dispatcher assignment is rebuilt.
+enum ResumeFrom {
+    /// Saved by the previous run (with its `saved_at`): keep its clocks and attempt.
+    Saved(SavedAssignment, u64),
+    /// Nothing saved: start the clocks now.
+    Fresh { attempt: u32 },
+}
+
 impl OrchestratorRuntime {
     /// Resume in_progress tasks from a previous session.
     pub async fn resume_in_progress_tasks(&mut self) {
@@ -28,30 +50,50 @@ impl OrchestratorRuntime {
                 return;
             }
         };
+        let saved = self.dispatcher.load_saved_state();
+        let note = resume_note(saved.as_ref());
         tracing::info!(
-            "Resuming {} in_progress tasks from previous session",
-            tasks.len()
+            "Resuming {} in_progress tasks from previous session ({})",
+            tasks.len(),
+            note
         );
         for task in &tasks {
-            self.resume_one_task(task).await;
+            if self.resume_one_task(task, saved.as_ref()).await {
+                let _ = self.db.add_comment(&task.id, "runtime", note).await;
+            }
         }
     }
 
-    async fn resume_one_task(&mut self, task: &llm_tasks::db::Task) {
+    /// Returns true if an agent was resumed on the task.
+    async fn resume_one_task(
+        &mut self,
+        task: &llm_tasks::db::Task,
+        saved: Option<&DispatchState>,
+    ) -> bool {
         let Some(assignee) = task.assignee.as_deref() else {
             self.reset_task_to_ready(&task.id).await;
-            return;
+            return false;
         };
         // Assignee should be "task-{id}" format
         if !assignee.starts_with("task-") {
             tracing::warn!("Cannot resume non-task assignee '{}'", assignee);
             self.reset_task_to_ready(&task.id).await;
-            return;
+            return false;
         }
-        if let Err(e) = self.spawn_resuming_agent(assignee, task) {
+        let from = match saved.and_then(|s| Some((s.assignment(&task.id)?, s.saved_at))) {
+            Some((assignment, saved_at)) if assignment.agent_name == assignee => {
+                ResumeFrom::Saved(assignment.clone(), saved_at)
+            }
+            _ => ResumeFrom::Fresh {
+                attempt: self.current_attempt(&task.id).await,
+            },
+        };
+        if let Err(e) = self.spawn_resuming_agent(assignee, task, from) {
             tracing::error!("Failed to resume {} on task {}: {}", assignee, task.id, e);
             self.reset_task_to_ready(&task.id).await;
+            return false;
         }
+        true
     }
 
     async fn reset_task_to_ready(&self, task_id: &str) {
@@ -63,29 +105,70 @@ impl OrchestratorRuntime {
         let _ = self.db.clear_assignee(task_id, "runtime").await;
     }
 
-    fn spawn_resuming_agent(&mut self, bus_name: &str, task: &llm_tasks::db::Task) -> Result<()> {
+    fn spawn_resuming_agent(
+        &mut self,
+        bus_name: &str,
+        task: &llm_tasks::db::Task,
+        from: ResumeFrom,
+    ) -> Result<()> {
         let task_id = bus_name.strip_prefix("task-").unwrap_or(&task.id);
         let agent_id = AgentId::for_task(task_id);
-        let target_branch = task.target_branch.as_deref().unwrap_or("master");
-        let (working_dir, sandbox_prefix, diff) = self.resume_worktree(bus_name, target_branch)?;
-        let prompt = build_task_resume_prompt(task, bus_name, &diff);
+        let target_branch = task
+            .target_branch
+            .as_deref()
+            .unwrap_or(&self.settings.default_branch);
+        let (working_dir, sandbox_prefix, work, post_create) =
+            self.resume_worktree(bus_name, target_branch)?;
+        let continue_session = matches!(
+            &from,
+            ResumeFrom::Saved(saved, _)
+                if session_resumable(saved, &self.settings.backend, support::unix_now())
+                    && conversation_stored(&self.session_store, &saved.session_key, &self.settings.backend)
+        );
+        let (kind, prompt) = if continue_session {
+            (
+                "task_resume",
+                build_session_resume_prompt(task, bus_name, target_branch),
+            )
+        } else {
+            (
+                "task_assignment",
+                build_task_resume_prompt(task, bus_name, target_branch, &work),
+            )
+        };
         let config = self.resume_agent_config(agent_id, working_dir, sandbox_prefix);
 
-        self.spawn_agent_with_config(config)?;
-        self.global_limits
-            .active_agents
-            .fetch_add(1, Ordering::Relaxed);
-        self.dispatcher
-            .register_active(task.id.clone(), bus_name.to_string());
+        let slot = self.global_limits.acquire_slot(&self.project);
+        self.spawn_agent_with_config(config, Some(slot), post_create)?;
+        match from {
+            ResumeFrom::Saved(assignment, saved_at) => {
+                self.dispatcher.restore_active(&assignment, saved_at)
+            }
+            ResumeFrom::Fresh { attempt } => {
+                self.dispatcher
+                    .register_active(task.id.clone(), bus_name.to_string(), attempt)
+            }
+        }
+        self.emit(
+            EventKind::AgentSpawned,
+            Some(&task.id),
+            Some(bus_name),
+            Some("resumed".to_string()),
+        );
         let payload = serde_json::json!({"content": prompt, "task_id": task.id});
-        if let Err(e) = self.dispatcher.notify(bus_name, "task_assignment", payload) {
+        if let Err(e) = self.dispatcher.notify(bus_name, kind, payload) {
             tracing::error!("Failed to send resume assignment to {}: {}", bus_name, e);
         }
         tracing::info!(
-            "Resumed {} on task {} (diff: {} bytes)",
+            "Resumed {} on task {} ({}, patch: {} chars)",
             bus_name,
             task.id,
-            diff.len()
+            if continue_session {
+                "continuing session"
+            } else {
+                "fresh session"
+            },
+            work.patch.chars().count()
         );
         Ok(())
     }
@@ -94,23 +177,29 @@ impl OrchestratorRuntime {
         &self,
         bus_name: &str,
         target_branch: &str,
-    ) -> Result<(String, Vec<String>, String)> {
+    ) -> Result<(String, Vec<String>, PriorWork, Option<PostCreate>)> {
         let project_path = PathBuf::from(&self.working_dir);
         let wt_cfg = WorktreeConfig {
             project_dir: project_path.clone(),
             agent_name: bus_name.to_string(),
             target_branch: target_branch.to_string(),
         };
-        let wt_path = worktree::create_or_resume_worktree(&wt_cfg)?;
-        let diff = worktree_diff(&wt_path, target_branch);
-        let use_sandbox = !self.no_sandbox && llm_sdk::sandbox::is_available();
-        let (wd, sp) = support::resolve_sandbox(
+        let prepared = worktree::create_or_resume_worktree(&wt_cfg, &self.settings.worktree)?;
+        let work = PriorWork::collect(&prepared.path, target_branch);
+        let use_sandbox = self.settings.sandbox && llm_sdk::sandbox::is_available();
+        let (wd, sp) = support::resolve_sandbox_with(
             AgentRole::TaskAgent,
             &project_path,
-            Ok(wt_path),
+            Ok(prepared.path.clone()),
             use_sandbox,
+            &self.sandbox_extras(bus_name),
         );
-        Ok((wd, sp, diff))
+        let post_create = if prepared.fresh {
+            self.post_create(prepared.path, &sp)
+        } else {
+            None
+        };
+        Ok((wd, sp, work, post_create))
     }
 
     fn resume_agent_config(
@@ -120,7 +209,7 @@ impl OrchestratorRuntime {
         sandbox_prefix: Vec<String>,
     ) -> AgentConfig {
         let bus_name = agent_id.bus_name();
-        let bus = match self.backend {
+        let bus = match self.settings.backend {
             BackendKind::OpenRouter { .. } | BackendKind::Codex { .. } => Some(self.bus.clone()),
             BackendKind::Claude => None,
         };
@@ -131,7 +220,7 @@ impl OrchestratorRuntime {
             initial_task: None,
             mcp_config: Some(support::build_mcp_config(&bus_name, &self.project)),
             fresh_session_per_task: true,
-            backend: self.backend.clone(),
+            backend: self.settings.backend.clone(),
             session_store: self.session_store.clone(),
             bus,
             sandbox_prefix,
@@ -139,43 +228,238 @@ impl OrchestratorRuntime {
     }
 }
 
-/// Get the diff between the target branch and the worktree's current state.
-fn worktree_diff(wt_path: &Path, target_branch: &str) -> String {
+/// What the previous session left in a task worktree.
+#[derive(Debug, Default)]
+struct PriorWork {
+    /// `git log --oneline` of the agent branch since the merge base.
+    log: String,
+    /// `git status --short`: uncommitted and untracked files.
+    uncommitted: String,
+    /// Patch from the merge base to the working tree, fitted to the budget.
+    patch: String,
+}
+
+impl PriorWork {
+    fn collect(wt_path: &Path, target_branch: &str) -> Self {
+        let base = git_output(wt_path, &["merge-base", target_branch, "HEAD"])
+            .map(|out| out.trim().to_string())
+            .unwrap_or_else(|| target_branch.to_string());
+        let range = format!("{base}..HEAD");
+        Self {
+            log: git_output(wt_path, &["log", "--oneline", &range]).unwrap_or_default(),
+            uncommitted: git_output(wt_path, &["status", "--short"]).unwrap_or_default(),
+            patch: budget_patch(
+                &git_output(wt_path, &["diff", &base]).unwrap_or_default(),
+                RESUME_PATCH_BUDGET,
+            ),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.log.trim().is_empty()
+            && self.uncommitted.trim().is_empty()
+            && self.patch.trim().is_empty()
+    }
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> Option<String> {
     let output = Command::new("git")
-        .args(["diff", target_branch, "--stat"])
-        .current_dir(wt_path)
-        .output();
-    match output {
-        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout).into_owned(),
-        _ => String::new(),
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Keep whole file diffs while they fit in `budget` characters; list the
+/// rest with their added/removed line counts.
+fn budget_patch(patch: &str, budget: usize) -> String {
+    if patch.chars().count() <= budget {
+        return patch.to_string();
+    }
+    let mut kept = String::new();
+    let mut used = 0;
+    let mut omitted = String::new();
+    for file in split_file_diffs(patch) {
+        let len = file.chars().count();
+        if used + len <= budget {
+            kept.push_str(file);
+            used += len;
+        } else {
+            omitted.push_str(&summarize_file_diff(file));
+            omitted.push('\n');
+        }
+    }
+    if !omitted.is_empty() {
+        kept.push_str("\n# Omitted to fit the prompt (run `git diff` to see them):\n");
+        kept.push_str(&claude_architect::truncate(&omitted, RESUME_LIST_MAX_CHARS));
+    }
+    kept
+}
+
+/// Split a unified patch into one chunk per `diff --git` header.
+fn split_file_diffs(patch: &str) -> Vec<&str> {
+    let mut starts: Vec<usize> = patch
+        .match_indices("diff --git ")
+        .map(|(idx, _)| idx)
+        .filter(|&idx| idx == 0 || patch.as_bytes()[idx - 1] == b'\n')
+        .collect();
+    if starts.first() != Some(&0) {
+        starts.insert(0, 0);
+    }
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| &patch[start..starts.get(n + 1).copied().unwrap_or(patch.len())])
+        .filter(|chunk| !chunk.is_empty())
+        .collect()
+}
+
+fn summarize_file_diff(file: &str) -> String {
+    let header = file.lines().next().unwrap_or_default();
+    let path = header
+        .strip_prefix("diff --git a/")
+        .and_then(|rest| rest.split(" b/").next())
+        .unwrap_or(header);
+    let (mut added, mut removed) = (0, 0);
+    for line in file.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            continue;
+        }
+        if line.starts_with('+') {
+            added += 1;
+        } else if line.starts_with('-') {
+            removed += 1;
+        }
     }
+    format!("- {path}: +{added} -{removed}")
 }
 
-fn build_task_resume_prompt(task: &llm_tasks::db::Task, bus_name: &str, diff: &str) -> String {
+fn build_task_resume_prompt(
+    task: &llm_tasks::db::Task,
+    bus_name: &str,
+    target: &str,
+    work: &PriorWork,
+) -> String {
     let desc = task.description.as_deref().unwrap_or("");
     let branch = format!("agent/{}", bus_name);
-    let target = task.target_branch.as_deref().unwrap_or("master");
-    let diff_section = if diff.is_empty() {
-        "No changes were committed yet on this branch.".to_string()
+    let work_section = if work.is_empty() {
+        "No changes were made yet on this branch.".to_string()
     } else {
-        let d = if diff.len() > 4000 {
-            &diff[..4000]
-        } else {
-            diff
-        };
-        format!(
-            "## Work already done (git diff {target} --stat)\n\n```\n{}\n```",
-            d
-        )
+        let mut section = String::new();
+        if !work.log.trim().is_empty() {
+            section.push_str(&format!(
+                "## Commits on `{branch}` since `{target}`\n\n```\n{}\n```\n\n",
+                claude_architect::truncate(work.log.trim_end(), RESUME_LIST_MAX_CHARS)
+            ));
+        }
+        if !work.uncommitted.trim().is_empty() {
+            section.push_str(&format!(
+                "## Uncommitted changes (git status --short)\n\n```\n{}\n```\n\n",
+                claude_architect::truncate(work.uncommitted.trim_end(), RESUME_LIST_MAX_CHARS)
+            ));
+        }
+        if !work.patch.trim().is_empty() {
+            section.push_str(&format!(
+                "## Patch against `{target}` (merge base to working tree)\n\n```diff\n{}\n```",
+                work.patch.trim_end()
+            ));
+        }
+        section.trim_end().to_string()
     };
     format!(
         "## RESUMING Task {id}\n\n{title}\n\n{desc}\n\n\
          This task was in progress in a previous session. The worktree on branch `{branch}` \
          has been preserved with any partial work.\n\n\
-         {diff_section}\n\n\
+         {work_section}\n\n\
          Review the existing changes, then continue where the previous session left off. \
          Commit your changes on branch `{branch}`.",
         id = task.id,
         title = task.title,
     )
 }
+
+/// Whether the agent's stored session or message log can be continued:
+/// written by the same backend under the agent's own key, and recent enough.
+fn session_resumable(saved: &SavedAssignment, backend: &BackendKind, now: u64) -> bool {
+    saved.session_key == saved.agent_name
+        && saved.backend == backend.name()
+        && now.saturating_sub(saved.last_heartbeat) <= SESSION_RESUME_MAX_AGE.as_secs()
+}
+
+/// Whether the store holds a conversation under `key` for `backend`: a
+/// Claude session that got its id from a first turn, or a non-empty message
+/// log. An agent that crashed before its first turn has neither.
+fn conversation_stored(store: &SessionStore, key: &str, backend: &BackendKind) -> bool {
+    match backend {
+        BackendKind::Claude => store.session(key).session_id().is_some(),
+        BackendKind::OpenRouter { .. } | BackendKind::Codex { .. } => {
+            !store.message_log(key).is_empty()
+        }
+    }
+}
+
+/// Short prompt for an agent that continues its own stored session.
+fn build_session_resume_prompt(
+    task: &llm_tasks::db::Task,
+    bus_name: &str,
+    target_branch: &str,
+) -> String {
+    let desc = task.description.as_deref().unwrap_or("");
+    format!(
+        "## RESUMING Task {id}\n\n{title}\n\n{desc}\n\n\
+         You were interrupted by an orchestrator restart while working on this task. \
+         Your worktree on branch `agent/{bus_name}` (based on `{target_branch}`) is intact. \
+         Check `git status` and `git log {target_branch}..HEAD`, then continue where you \
+         left off and commit your changes on that branch.",
+        id = task.id,
+        title = task.title,
+    )
+}
+
+/// Task comment explaining why an in-progress task was resumed.
+fn resume_note(saved: Option<&DispatchState>) -> &'static str {
+    match saved {
+        Some(state) if state.clean_shutdown => "Resumed after a clean daemon shutdown.",
+        Some(_) => "Resumed after the daemon stopped unexpectedly mid-task.",
+        None => "Resumed after a daemon restart (no saved dispatcher state).",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_diff(path: &str, added: usize) -> String {
+        let mut diff = format!(
+            "diff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n@@ -1 +1,{added} @@\n-old\n"
+        );
+        for n in 0..added {
+            diff.push_str(&format!("+line {n} é\n"));
+        }
+        diff
+    }
+
+    #[test]
+    fn small_patch_is_kept_whole() {
+        let patch = file_diff("src/lib.rs", 3);
+
+        assert_eq!(budget_patch(&patch, 10_000), patch);
+    }
+
+    #[test]
+    fn oversized_files_are_summarized() {
+        let small = file_diff("src/small.rs", 2);
+        let large = file_diff("src/large.rs", 500);
+        let patch = format!("{small}{large}");
+
+        let fitted = budget_patch(&patch, 1_000);
+
+        assert!(fitted.starts_with(&small));
+        assert!(!fitted.contains("line 499"));
+        assert!(fitted.contains("- src/large.rs: +500 -1"));
+    }
+}
@@ -0,0 +1,456 @@
+//! Reviewer backends for task validation and completion review.
+//!
+//! Each project picks one in `[<project>.reviewer]`: the claude-architect
+//! daemon (the default), the claude CLI, OpenRouter or Codex, with its own
+//! model and extra review instructions. The LLM backends share the prompts
+//! and verdict parsing; only the completion call differs.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use claude_architect::{
+    Request, Response, build_assessment_prompt, contains_incomplete, contains_needs_changes,
+    socket_path, truncate,
+};
+use llm_sdk::session::SessionStore;
+use peercred_ipc::Client;
+
+use crate::agent::BackendKind;
+use crate::config::{ReviewerBackend, ReviewerConfig};
+
+/// Model the claude CLI reviewer uses when none is configured.
+pub const DEFAULT_CLAUDE_REVIEW_MODEL: &str = "haiku";
+const VALIDATE_TIMEOUT: Duration = Duration::from_secs(180);
+
+pub enum ValidateResult {
+    Approved(String),
+    NeedsChanges(String),
+}
+
+pub enum ReviewResult {
+    Accomplished(String),
+    Incomplete(String),
+}
+
+/// A new task, before it is dispatched.
+pub struct TaskProposal<'a> {
+    pub project: &'a str,
+    pub title: &'a str,
+    pub description: &'a str,
+    pub cwd: &'a str,
+}
+
+/// A task an agent reported complete.
+pub struct CompletedWork<'a> {
+    pub project: &'a str,
+    pub title: &'a str,
+    pub dev_output: &'a str,
+    /// Outcome of the pre-review check, when the project runs one.
+    pub check_report: Option<&'a str>,
+    pub diff: &'a str,
+    pub cwd: &'a str,
+}
+
+#[async_trait]
+pub trait Reviewer: Send + Sync {
+    /// Decide whether a new task is clear enough to hand to an agent.
+    async fn validate(&self, task: &TaskProposal<'_>) -> Result<ValidateResult, String>;
+    /// Decide whether the agent's work accomplishes the task.
+    async fn review(&self, work: &CompletedWork<'_>) -> Result<ReviewResult, String>;
+}
+
+/// Build the reviewer configured for `project`.
+pub fn from_config(config: &ReviewerConfig, project: &str) -> Arc<dyn Reviewer> {
+    let model = config.model.as_deref();
+    let api_key = config.api_key.clone();
+    let instructions = config.prompt.clone();
+    match config.backend {
+        ReviewerBackend::Architect => Arc::new(ArchitectReviewer {
+            cli: LlmReviewer::new(ClaudeCli::new(model), instructions),
+        }),
+        ReviewerBackend::Claude => Arc::new(LlmReviewer::new(ClaudeCli::new(model), instructions)),
+        ReviewerBackend::OpenRouter => Arc::new(LlmReviewer::new(
+            SdkModel::new(
+                BackendKind::from_name("openrouter", model, api_key),
+                project,
+            ),
+            instructions,
+        )),
+        ReviewerBackend::Codex => Arc::new(LlmReviewer::new(
+            SdkModel::new(BackendKind::from_name("codex", model, None), project),
+            instructions,
+        )),
+    }
+}
+
+/// One-shot prompt completion behind the LLM reviewers.
+#[async_trait]
+trait Ask: Send + Sync {
+    async fn ask(&self, prompt: &str) -> Result<String, String>;
+}
+
+/// Reviewer that asks a model with the shared prompts.
+struct LlmReviewer<A> {
+    model: A,
+    /// Project-specific instructions appended to every prompt.
+    instructions: Option<String>,
+}
+
+impl<A> LlmReviewer<A> {
+    fn new(model: A, instructions: Option<String>) -> Self {
+        Self {
+            model,
+            instructions,
+        }
+    }
+
+    fn prompt(&self, base: String) -> String {
+        match &self.instructions {
+            Some(extra) => format!("{base}\n\n## Project review instructions\n{extra}"),
+            None => base,
+        }
+    }
+}
+
+#[async_trait]
+impl<A: Ask> Reviewer for LlmReviewer<A> {
+    async fn validate(&self, task: &TaskProposal<'_>) -> Result<ValidateResult, String> {
+        let verdict = self
+            .model
+            .ask(&self.prompt(validation_prompt(task)))
+            .await?;
+        if contains_needs_changes(&verdict) {
+            Ok(ValidateResult::NeedsChanges(verdict))
+        } else {
+            Ok(ValidateResult::Approved(verdict))
+        }
+    }
+
+    async fn review(&self, work: &CompletedWork<'_>) -> Result<ReviewResult, String> {
+        let assessment = self
+            .model
+            .ask(&self.prompt(assessment_prompt(work)))
+            .await?;
+        if contains_incomplete(&assessment) {
+            Ok(ReviewResult::Incomplete(assessment))
+        } else {
+            Ok(ReviewResult::Accomplished(assessment))
+        }
+    }
+}
+
+/// Validation through the claude-architect daemon, completion review through
+/// the claude CLI with the assessment reported back to the daemon.
+struct ArchitectReviewer {
+    cli: LlmReviewer<ClaudeCli>,
+}
+
+#[async_trait]
+impl Reviewer for ArchitectReviewer {
+    async fn validate(&self, task: &TaskProposal<'_>) -> Result<ValidateResult, String> {
+        let request = build_validate_request(task.project, task.title, task.description, task.cwd);
+        tokio::task::spawn_blocking(move || dispatch_validate(request))
+            .await
+            .map_err(|e| format!("join error: {e}"))?
+    }
+
+    async fn review(&self, work: &CompletedWork<'_>) -> Result<ReviewResult, String> {
+        let result = self.cli.review(work).await?;
+        let (ReviewResult::Accomplished(assessment) | ReviewResult::Incomplete(assessment)) =
+            &result;
+        report_to_daemon(work.project, work.title, assessment, work.cwd);
+        Ok(result)
+    }
+}
+
+/// `claude -p` with a fixed model.
+struct ClaudeCli {
+    model: String,
+}
+
+impl ClaudeCli {
+    fn new(model: Option<&str>) -> Self {
+        Self {
+            model: model.unwrap_or(DEFAULT_CLAUDE_REVIEW_MODEL).to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Ask for ClaudeCli {
+    async fn ask(&self, prompt: &str) -> Result<String, String> {
+        let output = tokio::process::Command::new("claude")
+            .arg("-p")
+            .arg(prompt)
+            .arg("--model")
+            .arg(&self.model)
+            .env_remove("CLAUDECODE")
+            .env_remove("CLAUDE_CODE_ENTRYPOINT")
+            .output()
+            .await
+            .map_err(|e| format!("failed to run claude: {e}"))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!(
+                "claude {} exited {}: {stderr}",
+                self.model, output.status
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+/// OpenRouter or Codex through llm-sdk. Every review gets a fresh message
+/// log, removed once the answer is in.
+struct SdkModel {
+    backend: BackendKind,
+    store: SessionStore,
+}
+
+impl SdkModel {
+    fn new(backend: BackendKind, project: &str) -> Self {
+        Self {
+            backend,
+            store: SessionStore::new("agent-orchestrator", project),
+        }
+    }
+}
+
+#[async_trait]
+impl Ask for SdkModel {
+    async fn ask(&self, prompt: &str) -> Result<String, String> {
+        let key = format!("reviewer-{}", uuid::Uuid::new_v4());
+        let output = match &self.backend {
+            BackendKind::OpenRouter { model, api_key } => {
+                let mut log = self.store.message_log(&key);
+                llm_sdk::openrouter::OpenRouter::new(model)
+                    .api_key(api_key)
+                    .complete_chat(&mut log, prompt)
+                    .await
+            }
+            BackendKind::Codex { model } => {
+                let log = Arc::new(Mutex::new(self.store.message_log(&key)));
+                llm_sdk::codex::Codex::new(model)
+                    .complete_with_log(prompt, log)
+                    .await
+            }
+            BackendKind::Claude => return Err("claude reviews go through the CLI".to_string()),
+        };
+        self.store.remove_message_log(&key);
+        let output = output.map_err(|e| format!("{} review failed: {e}", self.backend.name()))?;
+        Ok(output.text.trim().to_string())
+    }
+}
+
+fn validation_prompt(task: &TaskProposal<'_>) -> String {
+    let description = if task.description.is_empty() {
+        "(no description)"
+    } else {
+        task.description
+    };
+    format!(
+        "You review tasks before they are handed to an autonomous coding agent \
+         working in the {project} repository at {cwd}.\n\n\
+         ## Task\n{title}\n\n{description}\n\n\
+         Approve the task if it is clear, scoped and actionable as written. \
+         Otherwise say what is missing or ambiguous. End with exactly one line: \
+         `VERDICT: approved` or `VERDICT: needs-changes`.",
+        project = task.project,
+        cwd = task.cwd,
+        title = task.title,
+    )
+}
+
+fn assessment_prompt(work: &CompletedWork<'_>) -> String {
+    let mut combined = truncate(work.dev_output, 2000);
+    if let Some(report) = work.check_report {
+        combined.push_str(&format!("\n\n## Test results\n{}", truncate(report, 2000)));
+    }
+    let diff = truncate(work.diff, 4000);
+    if !diff.is_empty() {
+        combined.push_str(&format!("\n\n## Git diff\n```\n{diff}\n```"));
+    }
+    build_assessment_prompt(work.title, &combined)
+}
+
+fn build_validate_request(project: &str, title: &str, description: &str, cwd: &str) -> Request {
+    let task_summary = if description.is_empty() {
+        title.to_string()
+    } else {
+        format!("{title}: {description}")
+    };
+    Request::Validate {
+        project: project.to_string(),
+        goal: title.to_string(),
+        tasks: vec![task_summary],
+        cwd: cwd.to_string(),
+    }
+}
+
+fn dispatch_validate(request: Request) -> Result<ValidateResult, String> {
+    let path = socket_path();
+    match Client::call_timeout::<_, Request, Response>(&path, &request, VALIDATE_TIMEOUT) {
+        Ok(Response::Verdict(v)) if contains_needs_changes(&v) => {
+            Ok(ValidateResult::NeedsChanges(v))
+        }
+        Ok(Response::Verdict(v)) => Ok(ValidateResult::Approved(v)),
+        Ok(Response::Error(e)) => Err(format!("architect error: {e}")),
+        Ok(Response::Pong) => Err("unexpected pong".to_string()),
+        Err(e) => Err(format!("architect IPC error: {e}")),
+    }
+}
+
+/// Fire-and-forget report to the daemon for context.
+fn report_to_daemon(project: &str, task_title: &str, assessment: &str, cwd: &str) {
+    let report_req = Request::Report {
+        project: project.to_string(),
+        task_description: task_title.to_string(),
+        assessment: assessment.to_string(),
+        cwd: cwd.to_string(),
+    };
+    tokio::task::spawn_blocking(move || {
+        let path = socket_path();
+        let _ = Client::call_timeout::<_, Request, Response>(
+            &path,
+            &report_req,
+            Duration::from_secs(30),
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT_DIR: &str = "/syncthing/Sync/Projects/claude/agent-orchestrator";
+
+    fn daemon_available() -> bool {
+        let path = socket_path();
+        Client::call::<_, Request, Response>(&path, &Request::Ping)
+            .is_ok_and(|r| matches!(r, Response::Pong))
+    }
+
+    /// Canned answer standing in for a model.
+    struct Fixed(&'static str);
+
+    #[async_trait]
+    impl Ask for Fixed {
+        async fn ask(&self, _prompt: &str) -> Result<String, String> {
+            Ok(self.0.to_string())
+        }
+    }
+
+    fn proposal() -> TaskProposal<'static> {
+        TaskProposal {
+            project: "agent-orchestrator",
+            title: "Add retry logic to API calls",
+            description: "Wrap HTTP calls in architect_client.rs with exponential backoff",
+            cwd: PROJECT_DIR,
+        }
+    }
+
+    fn work() -> CompletedWork<'static> {
+        CompletedWork {
+            project: "agent-orchestrator",
+            title: "Add retry logic to API calls",
+            dev_output: "Added retry with exponential backoff to all HTTP calls. Tests pass.",
+            check_report: Some("Check `cargo test` passed."),
+            diff: "",
+            cwd: PROJECT_DIR,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // requires live claude-architect daemon
+    async fn architect_validate_returns_verdict() {
+        assert!(daemon_available(), "claude-architect daemon not running");
+        let reviewer = from_config(&ReviewerConfig::default(), "agent-orchestrator");
+        match reviewer.validate(&proposal()).await {
+            Ok(ValidateResult::Approved(v)) => {
+                assert!(v.contains("VERDICT"), "verdict missing VERDICT line: {v}");
+            }
+            Ok(ValidateResult::NeedsChanges(v)) => {
+                assert!(v.contains("VERDICT"), "verdict missing VERDICT line: {v}");
+                assert!(v.contains("needs-changes"));
+            }
+            Err(e) => panic!("validate failed: {e}"),
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // requires live daemon + claude CLI (cannot run inside Claude Code session)
+    async fn architect_review_returns_assessment() {
+        assert!(daemon_available(), "claude-architect daemon not running");
+        let reviewer = from_config(&ReviewerConfig::default(), "agent-orchestrator");
+        match reviewer.review(&work()).await {
+            Ok(ReviewResult::Accomplished(a)) => {
+                assert!(a.contains("ACCOMPLISHED"), "unexpected assessment: {a}");
+            }
+            Ok(ReviewResult::Incomplete(a)) => {
+                assert!(a.contains("INCOMPLETE"), "unexpected assessment: {a}");
+            }
+            Err(e) => panic!("review failed: {e}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn llm_reviewer_parses_verdicts() {
+        let approving = LlmReviewer::new(Fixed("Clear and scoped.\nVERDICT: approved"), None);
+        assert!(matches!(
+            approving.validate(&proposal()).await,
+            Ok(ValidateResult::Approved(_))
+        ));
+        let rejecting = LlmReviewer::new(Fixed("Which API?\nVERDICT: needs-changes"), None);
+        assert!(matches!(
+            rejecting.validate(&proposal()).await,
+            Ok(ValidateResult::NeedsChanges(_))
+        ));
+        let incomplete = LlmReviewer::new(Fixed("INCOMPLETE: no tests"), None);
+        assert!(matches!(
+            incomplete.review(&work()).await,
+            Ok(ReviewResult::Incomplete(_))
+        ));
+    }
+
+    #[test]
+    fn project_instructions_are_appended_to_prompts() {
+        let reviewer =
+            LlmReviewer::new(Fixed(""), Some("Reject changes without tests.".to_string()));
+        let prompt = reviewer.prompt(assessment_prompt(&work()));
+        assert!(prompt.contains("## Test results\nCheck `cargo test` passed."));
+        assert!(prompt.ends_with("## Project review instructions\nReject changes without tests."));
+    }
+
+    #[test]
+    fn build_validate_request_with_description() {
+        let req = build_validate_request("proj", "Fix bug", "null pointer in parser", "/tmp");
+        match req {
+            Request::Validate {
+                project,
+                goal,
+                tasks,
+                cwd,
+            } => {
+                assert_eq!(project, "proj");
+                assert_eq!(goal, "Fix bug");
+                assert_eq!(tasks, vec!["Fix bug: null pointer in parser"]);
+                assert_eq!(cwd, "/tmp");
+            }
+            _ => panic!("expected Validate"),
+        }
+    }
+
+    #[test]
+    fn build_validate_request_empty_description() {
+        let req = build_validate_request("proj", "Fix bug", "", "/tmp");
+        match req {
+            Request::Validate { tasks, .. } => {
+                assert_eq!(tasks, vec!["Fix bug"]);
+            }
+            _ => panic!("expected Validate"),
+        }
+    }
+}
@@ -6,11 +6,10 @@
 //! - Listens for runtime commands via its mailbox
 //! - Persists task history via llm-tasks
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use agent_bus::Bus;
 use anyhow::{Context, Result};
@@ -19,46 +18,98 @@ use llm_tasks::db::Database;
 use tokio::sync::watch;
 use tokio::task::JoinHandle;
 
-use crate::agent::{Agent, AgentConfig, BackendKind};
+use crate::agent::{Agent, AgentConfig, AgentExit, BackendKind, SHUTDOWN_REQUESTED};
 use crate::architect_client;
+use crate::config::ProjectSettings;
 use crate::control;
-use crate::dispatch::Dispatcher;
+use crate::dispatch::{self, Dispatcher};
+use crate::events::{EventHub, EventKind};
+use crate::gc;
 use crate::relay::{self, RelayServer};
+use crate::reviewer::{self, Reviewer};
 use crate::runtime_support::{self as support, CommandTimers};
 use crate::types::{AgentId, AgentRole};
-use crate::worktree::{self, WorktreeConfig};
-
-/// Maximum times a task can be dispatched before it's marked as failed.
-const MAX_TASK_ATTEMPTS: u32 = 3;
+use crate::worktree::{self, NativeMerge, WorktreeConfig};
+
+/// Status for reviewed tasks whose branch the merger could not land.
+pub const MERGE_CONFLICT_STATUS: &str = "merge_conflict";
+/// Status for tasks an operator cancelled without requeueing.
+pub const CANCELLED_STATUS: &str = "cancelled";
+const MERGE_COMMENT_MAX_CHARS: usize = 2000;
+const EVENT_DETAIL_MAX_CHARS: usize = 200;
+/// How long a merge may stay unreported before it is given up on.
+const PENDING_MERGE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// How long a released agent may take to exit before it is aborted.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+/// Stands in for the agent's report when a review is retried; the agent's
+/// own report is in the task comments.
+const UNREVIEWED_OUTPUT: &str =
+    "(Review retried after an earlier one gave no verdict; judge the work by its diff.)";
+/// How long the merger may take to finish its current merge on shutdown.
+pub const MERGER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+mod build_cache;
+mod limits;
+mod merge_check;
 mod retry_budget;
+mod revise;
+mod status;
+mod task_history;
+mod verify;
+
+use build_cache::BuildSlots;
+pub use limits::{AgentSlot, DEFAULT_PROJECT_PRIORITY, GlobalLimits, ProjectQuota};
+
+pub use retry_budget::{AttemptLimits, attempt_limits_path, set_max_attempts};
+use retry_budget::{AttemptRecord, attempts_since_manual_reset};
+use revise::ReviewHold;
 
-use retry_budget::count_attempts_since_manual_reset;
+/// Factory function that creates an Agent from config + mailbox.
+/// Tests inject a factory that uses FakeCompleter instead of real Claude.
+pub type AgentFactory = Arc<dyn Fn(AgentConfig, agent_bus::Mailbox) -> Result<Agent> + Send + Sync>;
 
-/// Global concurrency limits shared across all project runtimes.
-pub struct GlobalLimits {
-    pub max_concurrent: AtomicUsize,
-    pub active_agents: AtomicUsize,
+/// A merge handed to the merger or a post-merge check that has not reported back yet.
+#[derive(Clone)]
+struct PendingMerge {
+    task_id: String,
+    agent_name: String,
+    since: Instant,
 }
 
-impl GlobalLimits {
-    pub fn new(max: usize) -> Self {
+impl PendingMerge {
+    fn new(task_id: String, agent_name: String) -> Self {
         Self {
-            max_concurrent: AtomicUsize::new(max),
-            active_agents: AtomicUsize::new(0),
+            task_id,
+            agent_name,
+            since: Instant::now(),
         }
     }
 
-    /// How many more agents can be spawned globally.
-    pub fn available_slots(&self) -> usize {
-        let max = self.max_concurrent.load(Ordering::Relaxed);
-        let active = self.active_agents.load(Ordering::Relaxed);
-        max.saturating_sub(active)
+    fn branch(&self) -> String {
+        format!("agent/{}", self.agent_name)
     }
 }
 
-/// Factory function that creates an Agent from config + mailbox.
-/// Tests inject a factory that uses FakeCompleter instead of real Claude.
-pub type AgentFactory = Arc<dyn Fn(AgentConfig, agent_bus::Mailbox) -> Result<Agent> + Send + Sync>;
+/// An agent that reported completion and is expected to exit on its own.
+struct DrainingAgent {
+    name: String,
+    handle: JoinHandle<AgentExit>,
+    since: Instant,
+    /// Aborted but not yet unwound; its slot is released once it finishes.
+    aborted: bool,
+}
+
+/// Whether a ready task may be dispatched now.
+enum RetryGate {
+    /// Dispatch as attempt `attempt` (1-based) of `max_attempts`.
+    Dispatch { attempt: u32, max_attempts: u32 },
+    /// Still backing off after the previous attempt.
+    Wait(Duration),
+    /// Out of attempts; the task should be failed.
+    Exhausted {
+        history: Vec<AttemptRecord>,
+        max_attempts: u32,
+    },
+}
 
 /// Default factory: creates a real Agent backed by the configured backend.
 fn default_agent_factory() -> AgentFactory {
@@ -72,20 +123,50 @@ pub struct OrchestratorRuntime {
     pub(crate) session_store: SessionStore,
     pub(crate) working_dir: String,
     pub(crate) project: String,
-    agent_handles: HashMap<String, JoinHandle<()>>,
+    agent_handles: HashMap<String, JoinHandle<AgentExit>>,
+    /// Released agents still winding down; they hold their slot until they exit.
+    draining: Vec<DrainingAgent>,
     agent_factory: AgentFactory,
-    pub backend: BackendKind,
-    pub(crate) no_sandbox: bool,
+    /// Merges sent to the merger, oldest first (it processes them in order).
+    pending_merges: VecDeque<PendingMerge>,
+    /// Native merges whose post-merge check is running, by task.
+    merge_checks: HashMap<String, PendingMerge>,
+    /// Per-project settings resolved from projects.toml.
+    pub(crate) settings: ProjectSettings,
+    /// Validates new tasks and reviews completed ones.
+    reviewer: Arc<dyn Reviewer>,
+    /// Structured events for `watch` subscribers.
+    pub(crate) events: Arc<EventHub>,
     pub(crate) dispatcher: Dispatcher,
+    /// Shared target dir held by each task agent (`build_cache = "shared-target"`).
+    build_slots: BuildSlots,
+    /// Failed pre-review checks already sent back to the agent, per task.
+    check_followups: HashMap<String, u32>,
+    /// Agents kept alive while their completed task is reviewed, by task.
+    review_holds: HashMap<String, ReviewHold>,
+    /// Review rejections sent back to the agent in the current attempt, per task.
+    revisions: HashMap<String, u32>,
+    /// Tasks whose completion review is running.
+    reviews_in_flight: HashSet<String>,
+    /// Per-task attempt limits (`attempt_limits.json`); None in tests.
+    attempt_limits_path: Option<PathBuf>,
+    /// Attempt history of tasks waiting for dispatch, kept until they are
+    /// dispatched, leave the ready queue or get a task event.
+    attempt_histories: HashMap<String, Vec<AttemptRecord>>,
+    /// Startup validation of pending tasks was skipped while dispatch was
+    /// paused; it runs when dispatch resumes.
+    bootstrap_deferred: bool,
+    /// Running branch and worktree GC pass, if any.
+    gc: Option<JoinHandle<()>>,
 }
 
 impl OrchestratorRuntime {
     pub async fn new(
         db_path: &Path,
         working_dir: String,
-        backend: BackendKind,
-        no_sandbox: bool,
+        settings: ProjectSettings,
         global_limits: Arc<GlobalLimits>,
+        events: Arc<EventHub>,
     ) -> Result<Self> {
         let db = Database::open(db_path)
             .await
@@ -103,7 +184,10 @@ impl OrchestratorRuntime {
         let dispatch_mailbox = bus
             .register("dispatcher")
             .map_err(|e| anyhow::anyhow!("Failed to register dispatcher: {}", e))?;
-        let dispatcher = Dispatcher::new(db.clone(), dispatch_mailbox);
+        let dispatcher = Dispatcher::new(db.clone(), dispatch_mailbox, &settings)
+            .persist_to(db_path.with_file_name("dispatch_state.json"));
+        global_limits.configure_project(&project, settings.max_agents, settings.priority);
+        let reviewer = reviewer::from_config(&settings.reviewer, &project);
 
         Ok(Self {
             global_limits,
@@ -113,10 +197,23 @@ impl OrchestratorRuntime {
             working_dir,
             project,
             agent_handles: HashMap::new(),
+            draining: Vec::new(),
             agent_factory: default_agent_factory(),
-            backend,
-            no_sandbox,
+            pending_merges: VecDeque::new(),
+            merge_checks: HashMap::new(),
+            reviewer,
+            settings,
+            events,
             dispatcher,
+            build_slots: BuildSlots::default(),
+            check_followups: HashMap::new(),
+            review_holds: HashMap::new(),
+            reviews_in_flight: HashSet::new(),
+            revisions: HashMap::new(),
+            attempt_limits_path: Some(attempt_limits_path(db_path)),
+            attempt_histories: HashMap::new(),
+            bootstrap_deferred: false,
+            gc: None,
         })
     }
 
@@ -132,7 +229,12 @@ impl OrchestratorRuntime {
         let dispatch_mailbox = bus
             .register("dispatcher")
             .map_err(|e| anyhow::anyhow!("Failed to register dispatcher: {}", e))?;
-        let dispatcher = Dispatcher::new(db.clone(), dispatch_mailbox);
+        let settings = ProjectSettings {
+            backend,
+            sandbox: false,
+            ..Default::default()
+        };
+        let dispatcher = Dispatcher::new(db.clone(), dispatch_mailbox, &settings);
 
         Ok(Self {
             global_limits: Arc::new(GlobalLimits::new(10)),
@@ -142,10 +244,23 @@ impl OrchestratorRuntime {
             working_dir: working_dir.to_string(),
             project: "test".to_string(),
             agent_handles: HashMap::new(),
+            draining: Vec::new(),
             agent_factory: factory,
-            backend,
-            no_sandbox: true,
+            pending_merges: VecDeque::new(),
+            merge_checks: HashMap::new(),
+            reviewer: reviewer::from_config(&settings.reviewer, "test"),
+            settings,
+            events: Arc::new(EventHub::new()),
             dispatcher,
+            build_slots: BuildSlots::default(),
+            check_followups: HashMap::new(),
+            review_holds: HashMap::new(),
+            reviews_in_flight: HashSet::new(),
+            revisions: HashMap::new(),
+            attempt_limits_path: None,
+            attempt_histories: HashMap::new(),
+            bootstrap_deferred: false,
+            gc: None,
         })
     }
 
@@ -164,10 +279,23 @@ impl OrchestratorRuntime {
 
     /// Insert a fake agent handle for testing.
     pub fn insert_fake_handle(&mut self, name: &str) {
-        let handle = tokio::spawn(std::future::pending::<()>());
+        let handle = tokio::spawn(std::future::pending::<AgentExit>());
         self.agent_handles.insert(name.to_string(), handle);
     }
 
+    /// Replace the reviewer (for testing).
+    pub fn set_reviewer(&mut self, reviewer: Arc<dyn Reviewer>) {
+        self.reviewer = reviewer;
+    }
+
+    /// Queue a merge request as if the merger had been asked (for testing).
+    pub fn insert_pending_merge(&mut self, task_id: &str) {
+        self.pending_merges.push_back(PendingMerge::new(
+            task_id.to_string(),
+            AgentId::for_task(task_id).bus_name(),
+        ));
+    }
+
     /// Run in standalone mode.
     pub async fn run(self, _initial_task: Option<String>) -> Result<()> {
         let registry = control::new_registry();
@@ -180,6 +308,7 @@ impl OrchestratorRuntime {
         tokio::spawn(control::run_control_server(
             registry,
             self.global_limits.clone(),
+            self.events.clone(),
             shutdown_tx.clone(),
             shutdown_rx,
         ));
@@ -204,6 +333,7 @@ impl OrchestratorRuntime {
 
         self.resume_in_progress_tasks().await;
         self.bootstrap_pending_tasks().await;
+        self.retry_unreviewed_tasks().await;
         self.poll_dispatch().await;
         self.command_loop(&mut mailbox, shutdown_tx).await
     }
@@ -246,34 +376,128 @@ impl OrchestratorRuntime {
                     }
                 }
             }
+            self.report_background_work();
         }
 
         let _ = shutdown_tx.send(true);
+        self.global_limits.clear_demand(&self.project);
         self.shutdown().await;
         Ok(())
     }
 
     async fn poll_dispatch(&mut self) {
-        let available = self.global_limits.available_slots();
-        if available == 0 {
+        if self.global_limits.dispatch_blocked(&self.project) {
             return;
         }
-        let task_ids = self.dispatcher.tasks_to_dispatch(available).await;
-        for task_id in task_ids {
+        let mut candidates = Vec::new();
+        let limits = self
+            .attempt_limits_path
+            .as_deref()
+            .map(AttemptLimits::load)
+            .unwrap_or_default();
+        let ready = self.dispatcher.tasks_to_dispatch().await;
+        self.attempt_histories
+            .retain(|task_id, _| ready.contains(task_id));
+        for task_id in ready {
+            let max_attempts = limits.max_attempts(&task_id, self.settings.max_attempts);
+            match self.retry_gate(&task_id, max_attempts).await {
+                RetryGate::Dispatch {
+                    attempt,
+                    max_attempts,
+                } => candidates.push((task_id, attempt, max_attempts)),
+                RetryGate::Wait(remaining) => {
+                    tracing::debug!("Task {} backing off for {:?}", task_id, remaining);
+                }
+                RetryGate::Exhausted {
+                    history,
+                    max_attempts,
+                } => {
+                    tracing::error!(
+                        "Task {} exceeded max attempts ({}), marking failed",
+                        task_id,
+                        max_attempts
+                    );
+                    self.fail_task(&task_id, &history).await;
+                }
+            }
+        }
+        let granted = self
+            .global_limits
+            .request_slots(&self.project, candidates.len());
+        for (task_id, attempt, max_attempts) in candidates.into_iter().take(granted) {
             if self.global_limits.available_slots() == 0 {
                 break;
             }
-            if let Err(e) = self.spawn_task_agent(&task_id).await {
+            tracing::info!(
+                "Dispatching task {} (attempt {}/{})",
+                task_id,
+                attempt,
+                max_attempts
+            );
+            if let Err(e) = self.spawn_task_agent(&task_id, attempt).await {
                 tracing::error!("Failed to spawn agent for task {}: {}", task_id, e);
             }
         }
     }
 
+    /// Check the task's attempt budget against `max_attempts` and the
+    /// exponential backoff since its previous attempt ended.
+    async fn retry_gate(&mut self, task_id: &str, max_attempts: u32) -> RetryGate {
+        let history = match self.attempt_histories.get(task_id) {
+            Some(history) => history.clone(),
+            None => {
+                let history = match self.db.get_events(task_id).await {
+                    Ok(events) => attempts_since_manual_reset(&events),
+                    Err(_) => Vec::new(),
+                };
+                self.attempt_histories
+                    .insert(task_id.to_string(), history.clone());
+                history
+            }
+        };
+        let finished = history.len() as u32;
+        if finished >= max_attempts {
+            return RetryGate::Exhausted {
+                history,
+                max_attempts,
+            };
+        }
+        let backoff = retry_budget::retry_backoff(self.settings.retry_backoff, finished);
+        let ended_at = history
+            .last()
+            .and_then(|attempt| attempt.ended_at.as_deref())
+            .and_then(support::parse_db_timestamp);
+        if let Some(ended_at) = ended_at {
+            let waited = Duration::from_secs(support::unix_now().saturating_sub(ended_at));
+            if waited < backoff {
+                return RetryGate::Wait(backoff - waited);
+            }
+        }
+        RetryGate::Dispatch {
+            attempt: finished + 1,
+            max_attempts,
+        }
+    }
+
+    /// 1-based number of the attempt a task is on (or about to start).
+    pub(crate) async fn current_attempt(&self, task_id: &str) -> u32 {
+        let finished = match self.db.get_events(task_id).await {
+            Ok(events) => attempts_since_manual_reset(&events).len() as u32,
+            Err(_) => 0,
+        };
+        finished + 1
+    }
+
     async fn handle_task_event(&mut self, kind: &str, payload: &serde_json::Value, from: &str) {
+        let event_task_id = support::payload_str(payload, "task_id");
+        self.attempt_histories.remove(&event_task_id);
+        if matches!(kind, "task_done" | "review_rejected" | "review_unavailable") {
+            self.reviews_in_flight.remove(&event_task_id);
+        }
         let should_poll = match kind {
             "task_created" => {
                 let task_id = support::payload_str(payload, "task_id");
-                self.spawn_architect_validation(&task_id).await;
+                self.spawn_task_validation(&task_id).await;
                 false
             }
             "task_complete" => {
@@ -282,6 +506,7 @@ impl OrchestratorRuntime {
             }
             "task_done" => {
                 let task_id = support::payload_str(payload, "task_id");
+                self.release_review_hold(&task_id);
                 self.merge_agent_branch(&task_id).await;
                 true
             }
@@ -293,10 +518,22 @@ impl OrchestratorRuntime {
                 let agent = support::payload_str(payload, "agent");
                 if !agent.is_empty() {
                     self.dispatcher.record_activity(&agent);
+                    let task_id = self.dispatcher.task_id_for_agent_name(&agent);
+                    self.emit(EventKind::Heartbeat, task_id.as_deref(), Some(&agent), None);
                 }
                 false
             }
             "task_ready" => true,
+            "review_rejected" => {
+                self.handle_review_rejected(payload).await;
+                true
+            }
+            "review_unavailable" => {
+                // Strict reviewer: the task stays in review, its agent is done.
+                let task_id = support::payload_str(payload, "task_id");
+                self.release_review_hold(&task_id);
+                true
+            }
             _ => false,
         };
         if should_poll {
@@ -309,14 +546,42 @@ impl OrchestratorRuntime {
         let Some(task_id) = self.resolve_task_id(from) else {
             return;
         };
-        let agent_name = from.to_string();
-        self.release_agent(&agent_name);
-        if self
+        if self.start_verification(&task_id, from, &content) {
+            return;
+        }
+        self.finish_agent_task(&task_id, from, &content, None).await;
+    }
+
+    /// Hand a finished task to review, keeping its agent alive for a
+    /// revision when the project allows one.
+    async fn finish_agent_task(
+        &mut self,
+        task_id: &str,
+        agent_name: &str,
+        content: &str,
+        check_report: Option<String>,
+    ) {
+        let attempt = self
             .dispatcher
-            .handle_agent_complete(&task_id, &content)
-            .await
-        {
-            self.spawn_completion_review(&task_id, &content, &agent_name)
+            .assignments()
+            .into_iter()
+            .find(|info| info.task_id == task_id)
+            .map_or(1, |info| info.attempt);
+        self.emit(
+            EventKind::TaskCompleted,
+            Some(task_id),
+            Some(agent_name),
+            None,
+        );
+        let in_review = self
+            .dispatcher
+            .handle_agent_complete(task_id, content)
+            .await;
+        if !in_review || !self.hold_for_review(task_id, agent_name, attempt) {
+            self.release_agent(agent_name);
+        }
+        if in_review {
+            self.spawn_completion_review(task_id, content, agent_name, check_report)
                 .await;
         }
     }
@@ -326,18 +591,30 @@ impl OrchestratorRuntime {
         let agent_name = from.to_string();
         self.release_agent(&agent_name);
         if let Some(task_id) = self.resolve_task_id(from) {
+            self.emit(
+                EventKind::TaskBlocked,
+                Some(&task_id),
+                Some(&agent_name),
+                Some(claude_architect::truncate(&content, EVENT_DETAIL_MAX_CHARS)),
+            );
             self.dispatcher
                 .handle_agent_blocked(&task_id, &content)
                 .await;
         }
     }
 
+    /// Detach a finished agent. Deregistering its mailbox ends its run loop;
+    /// the handle is kept until then so the agent's slot is released on exit.
     fn release_agent(&mut self, agent_name: &str) {
-        self.agent_handles.remove(agent_name);
+        if let Some(handle) = self.agent_handles.remove(agent_name) {
+            self.draining.push(DrainingAgent {
+                name: agent_name.to_string(),
+                handle,
+                since: Instant::now(),
+                aborted: false,
+            });
+        }
         self.cleanup_agent_bus(agent_name);
-        self.global_limits
-            .active_agents
-            .fetch_sub(1, Ordering::Relaxed);
     }
 
     /// Resolve task ID from an agent's bus name.
@@ -351,21 +628,168 @@ impl OrchestratorRuntime {
         let timed_out = self.dispatcher.check_timeouts().await;
         for agent_name in &timed_out {
             tracing::warn!("Aborting timed-out agent {}", agent_name);
+            let task_id = self.resolve_task_id(agent_name);
+            self.emit(
+                EventKind::Timeout,
+                task_id.as_deref(),
+                Some(agent_name),
+                None,
+            );
             self.abort_agent(agent_name);
         }
-        if !timed_out.is_empty() {
+        let crashed = self.reap_crashed_agents().await;
+        self.reap_draining_agents();
+        self.expire_pending_merges().await;
+        self.reconcile_slots();
+        if !timed_out.is_empty() || crashed > 0 {
             self.poll_dispatch().await;
         }
     }
 
+    /// Task agents whose handle finished without reporting completion or a
+    /// block (panic, factory error, run loop exit). Their tasks go back to ready.
+    async fn reap_crashed_agents(&mut self) -> usize {
+        let crashed: Vec<String> = self
+            .agent_handles
+            .iter()
+            .filter(|(name, handle)| support::is_worktree_role(name) && handle.is_finished())
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in &crashed {
+            if self.drop_review_hold_of(name) {
+                // Its work is already in review; a rejection requeues the task.
+                self.release_agent(name);
+                continue;
+            }
+            tracing::warn!(
+                "Agent {} exited without reporting, reclaiming its task",
+                name
+            );
+            let task_id = self.dispatcher.task_id_for_agent_name(name);
+            self.emit(
+                EventKind::AgentCrashed,
+                task_id.as_deref(),
+                Some(name),
+                None,
+            );
+            if let Some(task_id) = task_id {
+                self.reclaim_crashed_task(&task_id, name).await;
+            }
+            self.abort_agent(name);
+        }
+        crashed.len()
+    }
+
+    async fn reclaim_crashed_task(&self, task_id: &str, agent_name: &str) {
+        let updates = llm_tasks::db::TaskUpdates {
+            status: Some("ready"),
+            ..Default::default()
+        };
+        if let Err(e) = self.db.update_task(task_id, updates, "runtime").await {
+            tracing::error!(
+                "Failed to reclaim task {} from {}: {}",
+                task_id,
+                agent_name,
+                e
+            );
+        }
+        let _ = self.db.clear_assignee(task_id, "runtime").await;
+        let _ = self
+            .db
+            .add_comment(
+                task_id,
+                "runtime",
+                &format!("Agent {agent_name} exited unexpectedly; task returned to ready"),
+            )
+            .await;
+    }
+
+    /// Drop exited draining agents and abort the ones that never exit.
+    /// Aborted agents stay listed until they finish so their slot still counts.
+    fn reap_draining_agents(&mut self) {
+        self.draining.retain_mut(|agent| {
+            if agent.handle.is_finished() {
+                return false;
+            }
+            if !agent.aborted && agent.since.elapsed() >= DRAIN_TIMEOUT {
+                tracing::warn!("Agent {} did not exit after release, aborting", agent.name);
+                agent.handle.abort();
+                agent.aborted = true;
+            }
+            true
+        });
+    }
+
+    /// Safety net: force the project's slot count to the number of live task agents.
+    fn reconcile_slots(&self) {
+        let live = self
+            .agent_handles
+            .iter()
+            .filter(|(name, handle)| support::is_worktree_role(name) && !handle.is_finished())
+            .count()
+            + self
+                .draining
+                .iter()
+                .filter(|agent| {
+                    support::is_worktree_role(&agent.name) && !agent.handle.is_finished()
+                })
+                .count();
+        if let Some(counted) = self.global_limits.reconcile_project(&self.project, live) {
+            tracing::warn!(
+                "Slot count for {} was {}, corrected to {} live agents",
+                self.project,
+                counted,
+                live
+            );
+        }
+    }
+
     async fn run_watchdog(&mut self) {
         let fixed = self.dispatcher.watchdog().await;
         if fixed > 0 {
             self.poll_dispatch().await;
         }
+        self.release_stale_review_holds().await;
+        self.retry_unreviewed_tasks().await;
+        self.collect_garbage();
     }
 
-    async fn spawn_architect_validation(&self, task_id: &str) {
+    /// Clean up branches and worktrees of finished tasks in the background,
+    /// one pass at a time.
+    fn collect_garbage(&mut self) {
+        if self.gc.as_ref().is_some_and(|handle| !handle.is_finished()) {
+            return;
+        }
+        let busy: HashSet<String> = self
+            .dispatcher
+            .assignments()
+            .into_iter()
+            .map(|info| info.task_id)
+            .chain(self.pending_merges.iter().map(|m| m.task_id.clone()))
+            .chain(self.merge_checks.keys().cloned())
+            .collect();
+        let db = self.db.clone();
+        let project_dir = PathBuf::from(&self.working_dir);
+        let default_branch = self.settings.default_branch.clone();
+        let setup = self.settings.worktree.clone();
+        self.gc = Some(tokio::spawn(async move {
+            let report =
+                match gc::collect_garbage(&db, &project_dir, &default_branch, &setup, &busy, false)
+                    .await
+                {
+                    Ok(report) => report,
+                    Err(e) => {
+                        tracing::warn!("Branch and worktree GC failed: {:#}", e);
+                        return;
+                    }
+                };
+            for line in report.lines() {
+                tracing::info!("GC: {}", line);
+            }
+        }));
+    }
+
+    async fn spawn_task_validation(&self, task_id: &str) {
         let task = match self.db.get_task(task_id).await {
             Ok(t) => t,
             Err(e) => {
@@ -376,13 +800,20 @@ impl OrchestratorRuntime {
         architect_client::spawn_validation(
             self.db.clone(),
             self.bus.clone(),
+            self.reviewer.clone(),
+            self.settings.reviewer.strict,
             self.project.clone(),
             self.working_dir.clone(),
             task,
         );
     }
 
-    pub async fn bootstrap_pending_tasks(&self) {
+    pub async fn bootstrap_pending_tasks(&mut self) {
+        self.bootstrap_deferred = self.global_limits.dispatch_blocked(&self.project);
+        if self.bootstrap_deferred {
+            tracing::info!("Dispatching paused, deferring pending task validation");
+            return;
+        }
         let tasks = match self.db.list_tasks(Some("pending"), None).await {
             Ok(tasks) => tasks,
             Err(e) => {
@@ -392,101 +823,133 @@ impl OrchestratorRuntime {
         };
 
         for task in tasks {
-            self.spawn_architect_validation(&task.id).await;
+            self.spawn_task_validation(&task.id).await;
         }
     }
 
     /// Spawn a fresh agent for a task.
-    async fn spawn_task_agent(&mut self, task_id: &str) -> Result<()> {
-        let attempts = self.count_attempts(task_id).await;
-        if attempts >= MAX_TASK_ATTEMPTS {
-            tracing::error!(
-                "Task {} exceeded max attempts ({}), marking failed",
-                task_id,
-                MAX_TASK_ATTEMPTS
-            );
-            self.fail_task(task_id).await;
-            return Ok(());
-        }
-        tracing::info!(
-            "Dispatching task {} (attempt {}/{})",
-            task_id,
-            attempts + 1,
-            MAX_TASK_ATTEMPTS
-        );
-
+    async fn spawn_task_agent(&mut self, task_id: &str, attempt: u32) -> Result<()> {
         let task = self
             .db
             .get_task(task_id)
             .await
             .context("Failed to get task for dispatch")?;
-        let target_branch = task.target_branch.as_deref().unwrap_or("master");
+        let target_branch = task
+            .target_branch
+            .as_deref()
+            .unwrap_or(&self.settings.default_branch);
 
         let agent_id = AgentId::for_task(task_id);
         let bus_name = agent_id.bus_name();
+        self.attempt_histories.remove(task_id);
+        // A new attempt gets its full revision budget.
+        self.release_review_hold(task_id);
+        // An answered question continues in the blocked agent's worktree.
+        let resume = match self.db.get_events(task_id).await {
+            Ok(events) => dispatch::resumes_after_answer(&events),
+            Err(_) => false,
+        };
 
-        if !self.dispatcher.claim_and_register(task_id, &bus_name).await {
+        if !self
+            .dispatcher
+            .claim_and_register(task_id, &bus_name, attempt)
+            .await
+        {
             return Ok(());
         }
 
-        self.global_limits
-            .active_agents
-            .fetch_add(1, Ordering::Relaxed);
-        let config = self.build_task_agent_config(agent_id, target_branch)?;
-        self.spawn_agent_with_config(config)?;
-        self.send_task_assignment(task_id, &bus_name).await;
+        let slot = self.global_limits.acquire_slot(&self.project);
+        let (config, post_create, kept_worktree) =
+            self.build_task_agent_config(agent_id, target_branch, resume)?;
+        self.emit(EventKind::TaskClaimed, Some(task_id), Some(&bus_name), None);
+        self.spawn_agent_with_config(config, Some(slot), post_create)?;
+        self.emit(
+            EventKind::AgentSpawned,
+            Some(task_id),
+            Some(&bus_name),
+            None,
+        );
+        self.send_task_assignment(task_id, &bus_name, resume, kept_worktree)
+            .await;
         Ok(())
     }
 
-    async fn fail_task(&self, task_id: &str) {
+    async fn fail_task(&self, task_id: &str, history: &[AttemptRecord]) {
         let updates = llm_tasks::db::TaskUpdates {
             status: Some("failed"),
             ..Default::default()
         };
         let _ = self.db.update_task(task_id, updates, "runtime").await;
-        let _ = self
-            .db
-            .add_comment(
-                task_id,
-                "runtime",
-                &format!("Failed after {} attempts", MAX_TASK_ATTEMPTS),
-            )
-            .await;
+        let comment = failure_comment(history);
+        let _ = self.db.add_comment(task_id, "runtime", &comment).await;
+        self.emit(EventKind::TaskFailed, Some(task_id), None, Some(comment));
     }
 
-    async fn count_attempts(&self, task_id: &str) -> u32 {
-        match self.db.get_events(task_id).await {
-            Ok(events) => count_attempts_since_manual_reset(&events),
-            Err(_) => 0,
-        }
+    /// Publish a structured event for `watch` subscribers.
+    pub(crate) fn emit(
+        &self,
+        kind: EventKind,
+        task_id: Option<&str>,
+        agent: Option<&str>,
+        detail: Option<String>,
+    ) {
+        self.events
+            .publish(&self.project, kind, task_id, agent, detail);
     }
 
+    /// The agent's config, its worktree's post-create command, and whether a
+    /// reused worktree was kept as-is with the previous attempt's work.
     fn build_task_agent_config(
         &self,
         agent_id: AgentId,
         target_branch: &str,
-    ) -> Result<AgentConfig> {
+        resume: bool,
+    ) -> Result<(AgentConfig, Option<PostCreate>, bool)> {
         let bus_name = agent_id.bus_name();
-        let (working_dir, sandbox_prefix) = self.working_dir_for_task(&bus_name, target_branch);
-        let bus = match self.backend {
+        let (working_dir, sandbox_prefix, prepared) =
+            self.working_dir_for_task(&bus_name, target_branch, resume);
+        let kept = prepared.as_ref().is_some_and(|p| p.kept);
+        let post_create = prepared
+            .filter(|p| p.fresh)
+            .and_then(|p| self.post_create(p.path, &sandbox_prefix));
+        let bus = match self.settings.backend {
             BackendKind::OpenRouter { .. } | BackendKind::Codex { .. } => Some(self.bus.clone()),
             BackendKind::Claude => None,
         };
-        Ok(AgentConfig {
+        let config = AgentConfig {
             agent_id,
             working_dir,
             system_prompt: AgentRole::TaskAgent.system_prompt().to_string(),
             initial_task: None,
             mcp_config: Some(support::build_mcp_config(&bus_name, &self.project)),
             fresh_session_per_task: true,
-            backend: self.backend.clone(),
+            backend: self.settings.backend.clone(),
             session_store: self.session_store.clone(),
             bus,
             sandbox_prefix,
+        };
+        Ok((config, post_create, kept))
+    }
+
+    /// The project's post-create command for a new worktree at `worktree`,
+    /// to run with the agent's sandbox `prefix`.
+    pub(crate) fn post_create(&self, worktree: PathBuf, prefix: &[String]) -> Option<PostCreate> {
+        let command = self.settings.worktree.post_create.clone()?;
+        Some(PostCreate {
+            command,
+            worktree,
+            prefix: prefix.to_vec(),
+            timeout: self.settings.worktree.post_create_timeout(),
         })
     }
 
-    async fn send_task_assignment(&self, task_id: &str, bus_name: &str) {
+    async fn send_task_assignment(
+        &self,
+        task_id: &str,
+        bus_name: &str,
+        resume: bool,
+        kept_worktree: bool,
+    ) {
         let task = match self.db.get_task(task_id).await {
             Ok(t) => t,
             Err(e) => {
@@ -496,9 +959,25 @@ impl OrchestratorRuntime {
         };
         let desc = task.description.as_deref().unwrap_or("");
         let branch = format!("agent/{}", bus_name);
+        let history = self
+            .task_history(task_id)
+            .await
+            .map(|section| format!("{section}\n"))
+            .unwrap_or_default();
+        let resume_note = if resume {
+            "Your question has been answered (see above). The branch and worktree \
+             still hold the work from before you asked; continue from there.\n\n"
+        } else if kept_worktree {
+            "Note: the previous attempt's uncommitted changes could not be saved, so \
+             the worktree was kept as it was, on that attempt's branch state rather than \
+             a clean checkout of the target branch. Check `git status` and `git log` \
+             first and decide what to keep before starting.\n\n"
+        } else {
+            ""
+        };
         let content = format!(
-            "## Task {}\n\n{}\n\n{}\n\nCommit your changes on branch `{}`.",
-            task_id, task.title, desc, branch
+            "## Task {}\n\n{}\n\n{}\n\n{}{}Commit your changes on branch `{}`.",
+            task_id, task.title, desc, history, resume_note, branch
         );
         let payload = serde_json::json!({"content": content, "task_id": task_id});
         if let Err(e) = self.dispatcher.notify(bus_name, "task_assignment", payload) {
@@ -527,38 +1006,271 @@ impl OrchestratorRuntime {
             tracing::warn!("Task {task_id} has no task agent assignee, skipping merge");
             return;
         }
+        let merge = PendingMerge::new(task_id.to_string(), assignee);
+        let target = task
+            .target_branch
+            .unwrap_or_else(|| self.settings.default_branch.clone());
+        // Only merge natively while no other merge is in flight, so we never
+        // race the merger's checkout or another post-merge check.
+        let native = self.pending_merges.is_empty() && self.merge_checks.is_empty();
+        let handed_off = if native && self.start_merge_check(&merge, &target) {
+            true
+        } else if native && self.try_native_merge(&merge, &target).await {
+            true
+        } else {
+            self.send_merge_request(merge, &target)
+        };
+        if !handed_off {
+            return;
+        }
+        if let Err(e) = self.db.clear_assignee(task_id, "runtime").await {
+            tracing::warn!("Failed to clear assignee for merged task {task_id}: {e}");
+        }
+    }
+
+    /// Hand a branch to the LLM merger. Returns false if the request could not be sent.
+    fn send_merge_request(&mut self, merge: PendingMerge, target: &str) -> bool {
         self.ensure_merger();
-        let branch = format!("agent/{}", assignee);
-        let target = task.target_branch.as_deref().unwrap_or("master");
         let payload = serde_json::json!({
-            "branch": branch,
+            "branch": merge.branch(),
             "target_branch": target,
-            "description": format!("Merge reviewed task {task_id}"),
-            "from_agent": assignee,
+            "description": format!("Merge reviewed task {}", merge.task_id),
+            "from_agent": merge.agent_name,
         });
         if let Err(e) = self.dispatcher.notify("merger", "merge_request", payload) {
-            tracing::error!("Failed to send merge_request for {task_id}: {e}");
-        } else if let Err(e) = self.db.clear_assignee(task_id, "runtime").await {
-            tracing::warn!("Failed to clear assignee for merged task {task_id}: {e}");
+            tracing::error!("Failed to send merge_request for {}: {e}", merge.task_id);
+            return false;
         }
+        self.pending_merges.push_back(merge);
+        true
     }
 
-    async fn spawn_completion_review(&self, task_id: &str, dev_output: &str, agent_name: &str) {
+    /// Fast-forward or cleanly merge a task branch without the merger agent.
+    /// Used when the project has no post-merge check. Returns false when the
+    /// merger is needed (conflicts, or the checkout can't host the merge).
+    async fn try_native_merge(&self, merge: &PendingMerge, target: &str) -> bool {
+        let (dir, branch, target_branch) = (
+            PathBuf::from(&self.working_dir),
+            merge.branch(),
+            target.to_string(),
+        );
+        let outcome = tokio::task::spawn_blocking(move || {
+            worktree::try_native_merge(&dir, &branch, &target_branch)
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+
+        match outcome {
+            Ok(NativeMerge::Merged) => {
+                let summary = format!("Merged {} into {target} without conflicts", merge.branch());
+                self.finish_merged_task(merge, &summary).await;
+                true
+            }
+            Ok(NativeMerge::Conflicts) => {
+                tracing::info!(
+                    "Task {} has merge conflicts, using merger agent",
+                    merge.task_id
+                );
+                false
+            }
+            Ok(NativeMerge::Unavailable(reason)) => {
+                tracing::info!("Native merge unavailable for {}: {}", merge.task_id, reason);
+                false
+            }
+            Err(e) => {
+                tracing::warn!("Native merge failed for {}: {}", merge.task_id, e);
+                false
+            }
+        }
+    }
+
+    /// Give up on merges that never reported back (merger crashed or lost the
+    /// request, check result lost), so they don't block native merging for good.
+    async fn expire_pending_merges(&mut self) {
+        let (expired, kept): (Vec<PendingMerge>, VecDeque<PendingMerge>) = self
+            .pending_merges
+            .drain(..)
+            .partition(|merge| merge.since.elapsed() >= PENDING_MERGE_TIMEOUT);
+        self.pending_merges = kept;
+        let stale_checks: Vec<String> = self
+            .merge_checks
+            .iter()
+            .filter(|(_, merge)| merge.since.elapsed() >= PENDING_MERGE_TIMEOUT)
+            .map(|(task_id, _)| task_id.clone())
+            .collect();
+        let expired = expired.into_iter().chain(
+            stale_checks
+                .iter()
+                .filter_map(|task_id| self.merge_checks.remove(task_id)),
+        );
+        for merge in expired.collect::<Vec<_>>() {
+            tracing::warn!(
+                "Merge of {} never reported back, giving up on it",
+                merge.branch()
+            );
+            let note = format!(
+                "No merge result after {} minutes; the merge was abandoned.",
+                PENDING_MERGE_TIMEOUT.as_secs() / 60
+            );
+            self.mark_merge_conflict(&merge, &note).await;
+        }
+    }
+
+    /// Apply the merger's `merge_success` / `merge_failed` report to its task.
+    async fn handle_merge_result(&mut self, kind: &str, payload: &serde_json::Value) {
+        let content = support::payload_str(payload, "content");
+        let Some(merge) = self.take_pending_merge(&content) else {
+            tracing::warn!("Received {kind} that matches no pending merge: {content}");
+            return;
+        };
+        if kind == "merge_success" {
+            self.finish_merged_task(&merge, &content).await;
+        } else {
+            self.mark_merge_conflict(&merge, &content).await;
+        }
+    }
+
+    /// Match a merger report to a pending merge by the branch it mentions.
+    /// Only a report naming no branch settles the oldest request; one naming
+    /// a branch that isn't queued (after a restart) settles that branch alone.
+    fn take_pending_merge(&mut self, content: &str) -> Option<PendingMerge> {
+        let mentioned = support::mentioned_task_ids(content);
+        if mentioned.is_empty() {
+            return self.pending_merges.pop_front();
+        }
+        if let Some(idx) = self
+            .pending_merges
+            .iter()
+            .position(|m| mentioned.contains(&m.task_id))
+        {
+            return self.pending_merges.remove(idx);
+        }
+        let task_id = mentioned.into_iter().next()?;
+        tracing::warn!("Merger reported on {task_id}, which has no queued merge request");
+        Some(PendingMerge::new(
+            task_id.clone(),
+            AgentId::for_task(&task_id).bus_name(),
+        ))
+    }
+
+    async fn finish_merged_task(&self, merge: &PendingMerge, content: &str) {
+        let branch = merge.branch();
+        let short = claude_architect::truncate(content, MERGE_COMMENT_MAX_CHARS);
+        let _ = self
+            .db
+            .add_comment(
+                &merge.task_id,
+                "merger",
+                &format!("Merged {branch}: {short}"),
+            )
+            .await;
+        tracing::info!("Task {} merged, cleaning up {}", merge.task_id, branch);
+        self.emit(
+            EventKind::MergeResult,
+            Some(&merge.task_id),
+            Some(&merge.agent_name),
+            Some(format!("merged {branch}")),
+        );
+        self.try_remove_worktree(&merge.agent_name);
+        if let Err(e) = worktree::delete_branch(Path::new(&self.working_dir), &branch) {
+            tracing::warn!("Failed to delete merged branch {}: {}", branch, e);
+        }
+    }
+
+    async fn mark_merge_conflict(&self, merge: &PendingMerge, content: &str) {
+        let updates = llm_tasks::db::TaskUpdates {
+            status: Some(MERGE_CONFLICT_STATUS),
+            ..Default::default()
+        };
+        if let Err(e) = self.db.update_task(&merge.task_id, updates, "merger").await {
+            tracing::error!(
+                "Failed to set task {} {}: {}",
+                merge.task_id,
+                MERGE_CONFLICT_STATUS,
+                e
+            );
+        }
+        let short = claude_architect::truncate(content, MERGE_COMMENT_MAX_CHARS);
+        let _ = self
+            .db
+            .add_comment(
+                &merge.task_id,
+                "merger",
+                &format!("Merge of {} failed: {short}", merge.branch()),
+            )
+            .await;
+        tracing::warn!(
+            "Task {} merge failed, worktree kept for resolution",
+            merge.task_id
+        );
+        self.emit(
+            EventKind::MergeResult,
+            Some(&merge.task_id),
+            Some(&merge.agent_name),
+            Some(format!("conflict on {}", merge.branch())),
+        );
+    }
+
+    /// Tell the daemon how many reviews and merges are still running, so a
+    /// drain waits for them. Queued merges are the merger's outstanding work.
+    fn report_background_work(&self) {
+        let count =
+            self.reviews_in_flight.len() + self.pending_merges.len() + self.merge_checks.len();
+        self.global_limits
+            .report_background_work(&self.project, count);
+    }
+
+    /// Review again the in_review tasks no review is running for: those a
+    /// strict reviewer left without a verdict, or whose review a restart lost.
+    pub async fn retry_unreviewed_tasks(&mut self) {
+        let tasks = match self.db.list_tasks(Some("in_review"), None).await {
+            Ok(tasks) => tasks,
+            Err(e) => {
+                tracing::error!("Failed to query in_review tasks: {}", e);
+                return;
+            }
+        };
+        let in_review: HashSet<String> = tasks.into_iter().map(|t| t.id).collect();
+        self.reviews_in_flight.retain(|id| in_review.contains(id));
+        for task_id in in_review {
+            if self.reviews_in_flight.contains(&task_id) {
+                continue;
+            }
+            tracing::info!("Retrying review of {} (no verdict yet)", task_id);
+            let agent_name = AgentId::for_task(&task_id).bus_name();
+            self.spawn_completion_review(&task_id, UNREVIEWED_OUTPUT, &agent_name, None)
+                .await;
+        }
+    }
+
+    async fn spawn_completion_review(
+        &mut self,
+        task_id: &str,
+        dev_output: &str,
+        agent_name: &str,
+        check_report: Option<String>,
+    ) {
         let target_branch = self
             .db
             .get_task(task_id)
             .await
             .ok()
             .and_then(|t| t.target_branch)
-            .unwrap_or_else(|| "master".to_string());
+            .unwrap_or_else(|| self.settings.default_branch.clone());
         let branch = format!("agent/{}", agent_name);
+        self.reviews_in_flight.insert(task_id.to_string());
         architect_client::spawn_review(architect_client::ReviewJob {
             db: self.db.clone(),
+            events: self.events.clone(),
             bus: self.bus.clone(),
+            reviewer: self.reviewer.clone(),
+            strict: self.settings.reviewer.strict,
             project: self.project.clone(),
             cwd: self.working_dir.clone(),
             task_id: task_id.to_string(),
             dev_output: dev_output.to_string(),
+            check_report,
             target_branch,
             branch,
         });
@@ -574,9 +1286,21 @@ impl OrchestratorRuntime {
         tracing::info!("Runtime received '{}' from {}", kind, from);
         match kind {
             "task_created" | "task_ready" | "task_done" | "task_complete" | "task_blocked"
-            | "agent_heartbeat" => {
+            | "review_rejected" | "review_unavailable" | "agent_heartbeat" => {
                 self.handle_task_event(kind, payload, from).await;
             }
+            "merge_success" | "merge_failed" => self.handle_merge_result(kind, payload).await,
+            "merge_check_result" => self.handle_merge_check_result(payload).await,
+            "check_result" => self.handle_check_result(payload).await,
+            "status_request" => self.reply_status(from).await,
+            "cancel_task" => self.cancel_task(payload, from).await,
+            "dispatch_resumed" => {
+                // Tasks created since startup are validated as they arrive.
+                if self.bootstrap_deferred {
+                    self.bootstrap_pending_tasks().await;
+                }
+                self.poll_dispatch().await;
+            }
             _ => tracing::debug!("Runtime ignoring unknown kind: {}", kind),
         }
         false
@@ -603,18 +1327,66 @@ impl OrchestratorRuntime {
         }
         tracing::info!("Shutting down {} agents", self.agent_handles.len());
         let merger_handle = self.agent_handles.remove("merger");
-        for (name, handle) in self.agent_handles.drain() {
-            tracing::info!("Stopping {}", name);
-            handle.abort();
+        for agent in self.draining.drain(..) {
+            agent.handle.abort();
         }
+        self.stop_task_agents().await;
         if let Some(handle) = merger_handle {
-            tracing::info!("Waiting for merger to finish (30s timeout)");
-            match tokio::time::timeout(Duration::from_secs(30), handle).await {
+            tracing::info!(
+                "Waiting for merger to finish ({}s timeout)",
+                MERGER_SHUTDOWN_TIMEOUT.as_secs()
+            );
+            match tokio::time::timeout(MERGER_SHUTDOWN_TIMEOUT, handle).await {
                 Ok(_) => tracing::info!("Merger finished cleanly"),
                 Err(_) => tracing::warn!("Merger timed out, aborting"),
             }
         }
         self.try_remove_worktree("merger");
+        self.dispatcher.mark_clean_shutdown();
+    }
+
+    /// Ask task agents to commit their work, wait out the grace period, then
+    /// abort the rest. Each interrupted task gets a comment saying how it ended.
+    async fn stop_task_agents(&mut self) {
+        let grace = self.settings.shutdown_grace;
+        let agents: Vec<(String, JoinHandle<AgentExit>)> = self.agent_handles.drain().collect();
+        if !grace.is_zero() {
+            for (name, _) in &agents {
+                let _ = self
+                    .dispatcher
+                    .notify(name, SHUTDOWN_REQUESTED, serde_json::json!({}));
+            }
+        }
+        let deadline = tokio::time::Instant::now() + grace;
+        for (name, mut handle) in agents {
+            let exit = match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(joined) => Some(joined.unwrap_or(AgentExit::Stopped)),
+                Err(_) => {
+                    tracing::info!("Stopping {} after {}s grace", name, grace.as_secs());
+                    handle.abort();
+                    None
+                }
+            };
+            let Some(task_id) = self.dispatcher.task_id_for_agent_name(&name) else {
+                continue;
+            };
+            let note = match exit {
+                Some(AgentExit::Shutdown { committed: true }) => {
+                    "Interrupted by daemon shutdown; the agent stopped after committing its work in progress.".to_string()
+                }
+                Some(AgentExit::Shutdown { committed: false }) => {
+                    "Interrupted by daemon shutdown; the agent stopped without committing, uncommitted changes stay in its worktree.".to_string()
+                }
+                Some(AgentExit::Stopped) => {
+                    "Interrupted by daemon shutdown; the agent exited before it could commit its work in progress.".to_string()
+                }
+                None => format!(
+                    "Interrupted by daemon shutdown; the agent was aborted after a {}s grace period.",
+                    grace.as_secs()
+                ),
+            };
+            let _ = self.db.add_comment(&task_id, "runtime", &note).await;
+        }
     }
 
     fn ensure_merger(&mut self) {
@@ -635,8 +1407,9 @@ impl OrchestratorRuntime {
     fn spawn_merger(&mut self) -> Result<()> {
         let agent_id = AgentId::merger();
         let bus_name = agent_id.bus_name();
-        let (working_dir, sandbox_prefix) = self.working_dir_for_task(&bus_name, "master");
-        let bus = match self.backend {
+        let (working_dir, sandbox_prefix, _) =
+            self.working_dir_for_task(&bus_name, &self.settings.default_branch, false);
+        let bus = match self.settings.backend {
             BackendKind::OpenRouter { .. } | BackendKind::Codex { .. } => Some(self.bus.clone()),
             BackendKind::Claude => None,
         };
@@ -647,16 +1420,24 @@ impl OrchestratorRuntime {
             initial_task: None,
             mcp_config: Some(support::build_mcp_config(&bus_name, &self.project)),
             fresh_session_per_task: false,
-            backend: self.backend.clone(),
+            backend: self.settings.backend.clone(),
             session_store: self.session_store.clone(),
             bus,
             sandbox_prefix,
         };
-        self.spawn_agent_with_config(config)
+        self.spawn_agent_with_config(config, None, None)
     }
 
-    fn working_dir_for_task(&self, bus_name: &str, target_branch: &str) -> (String, Vec<String>) {
-        let use_sandbox = !self.no_sandbox && llm_sdk::sandbox::is_available();
+    /// Worktree and sandbox prefix for an agent, plus the prepared worktree
+    /// when there is one; `resume` keeps the branch state of an existing
+    /// worktree instead of resetting it.
+    fn working_dir_for_task(
+        &self,
+        bus_name: &str,
+        target_branch: &str,
+        resume: bool,
+    ) -> (String, Vec<String>, Option<worktree::PreparedWorktree>) {
+        let use_sandbox = self.settings.sandbox && llm_sdk::sandbox::is_available();
         let project_path = PathBuf::from(&self.working_dir);
 
         let worktree_result = if support::is_worktree_role(bus_name) {
@@ -665,7 +1446,12 @@ impl OrchestratorRuntime {
                 agent_name: bus_name.to_string(),
                 target_branch: target_branch.to_string(),
             };
-            worktree::create_worktree(&cfg).map_err(|e| {
+            let created = if resume {
+                worktree::create_or_resume_worktree(&cfg, &self.settings.worktree)
+            } else {
+                worktree::create_worktree(&cfg, &self.settings.worktree)
+            };
+            created.map_err(|e| {
                 tracing::warn!(
                     "Failed to create worktree for {}, using project dir: {}",
                     bus_name,
@@ -676,37 +1462,136 @@ impl OrchestratorRuntime {
         } else {
             Err(anyhow::anyhow!("not a worktree role"))
         };
+        let prepared = worktree_result.as_ref().ok().cloned();
 
-        support::resolve_sandbox(
+        let (working_dir, prefix) = support::resolve_sandbox_with(
             AgentRole::TaskAgent,
             &project_path,
-            worktree_result,
+            worktree_result.map(|prepared| prepared.path),
             use_sandbox,
-        )
+            &self.sandbox_extras(bus_name),
+        );
+        (working_dir, prefix, prepared)
     }
 
-    pub(crate) fn spawn_agent_with_config(&mut self, config: AgentConfig) -> Result<()> {
+    pub(crate) fn sandbox_extras(&self, bus_name: &str) -> support::SandboxExtras {
+        let cache = self.build_cache_extras(bus_name);
+        let mut mounts = self.settings.extra_mounts.clone();
+        mounts.extend(cache.mounts);
+        support::SandboxExtras {
+            mounts,
+            env: cache.env,
+        }
+    }
+
+    /// Spawn an agent task. Task agents pass their slot, which is held by the
+    /// task itself and released when it ends, however it ends. A new
+    /// worktree's post-create command runs first; messages queue meanwhile.
+    pub(crate) fn spawn_agent_with_config(
+        &mut self,
+        config: AgentConfig,
+        slot: Option<AgentSlot>,
+        post_create: Option<PostCreate>,
+    ) -> Result<()> {
         let bus_name = config.agent_id.bus_name();
         let mailbox = self
             .bus
             .register(&bus_name)
             .map_err(|e| anyhow::anyhow!("Failed to register {}: {}", bus_name, e))?;
         let factory = self.agent_factory.clone();
-        let handle = tokio::spawn(run_agent(factory, config, mailbox));
+        let handle = tokio::spawn(run_agent(factory, config, mailbox, slot, post_create));
 
         self.agent_handles.insert(bus_name, handle);
         Ok(())
     }
 
+    /// Stop a task's agent on operator request and requeue or cancel the
+    /// task, then tell the requester (`cancel_reply`) whether it worked.
+    async fn cancel_task(&mut self, payload: &serde_json::Value, from: &str) {
+        let task_id = support::payload_str(payload, "task_id");
+        let requeue = payload
+            .get("requeue")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let error = match self.try_cancel_task(&task_id, requeue).await {
+            Ok(()) => None,
+            Err(message) => {
+                tracing::warn!("Cannot cancel task {}: {}", task_id, message);
+                Some(message)
+            }
+        };
+        let reply = serde_json::json!({"task_id": task_id, "error": error});
+        if let Err(e) = self.dispatcher.notify(from, "cancel_reply", reply) {
+            tracing::debug!("Failed to send cancel reply to {}: {}", from, e);
+        }
+    }
+
+    async fn try_cancel_task(&mut self, task_id: &str, requeue: bool) -> Result<(), String> {
+        let task = self.db.get_task(task_id).await.map_err(|e| e.to_string())?;
+        if matches!(task.status.as_str(), "done" | CANCELLED_STATUS) {
+            return Err(format!("task {task_id} is already {}", task.status));
+        }
+
+        let agent_name = AgentId::for_task(task_id).bus_name();
+        self.abort_agent(&agent_name);
+        for agent in self.draining.iter_mut().filter(|a| a.name == agent_name) {
+            agent.handle.abort();
+            agent.aborted = true;
+        }
+        self.forget_task(task_id);
+
+        let status = if requeue { "ready" } else { CANCELLED_STATUS };
+        let updates = llm_tasks::db::TaskUpdates {
+            status: Some(status),
+            ..Default::default()
+        };
+        self.db
+            .update_task(task_id, updates, "user")
+            .await
+            .map_err(|e| e.to_string())?;
+        let _ = self.db.clear_assignee(task_id, "user").await;
+        let note = if requeue {
+            "Cancelled by operator and requeued"
+        } else {
+            "Cancelled by operator"
+        };
+        let _ = self.db.add_comment(task_id, "user", note).await;
+        tracing::info!("Cancelled task {} (requeue: {})", task_id, requeue);
+        self.emit(
+            EventKind::TaskCancelled,
+            Some(task_id),
+            Some(&agent_name),
+            Some(status.to_string()),
+        );
+        Ok(())
+    }
+
+    /// Drop the runtime's per-task bookkeeping: queued merges and merge
+    /// checks, review and revision state, and check follow-ups.
+    fn forget_task(&mut self, task_id: &str) {
+        self.pending_merges.retain(|merge| merge.task_id != task_id);
+        self.merge_checks.remove(task_id);
+        self.review_holds.remove(task_id);
+        self.revisions.remove(task_id);
+        self.check_followups.remove(task_id);
+        self.reviews_in_flight.remove(task_id);
+        self.attempt_histories.remove(task_id);
+    }
+
+    /// Abort an agent and drop its bus names and worktree. The handle moves
+    /// to `draining` until the aborted task has unwound and freed its slot.
     fn abort_agent(&mut self, name: &str) {
         if let Some(handle) = self.agent_handles.remove(name) {
             tracing::info!("Stopping {}", name);
             handle.abort();
+            self.draining.push(DrainingAgent {
+                name: name.to_string(),
+                handle,
+                since: Instant::now(),
+                aborted: true,
+            });
         }
         self.cleanup_agent_bus(name);
-        self.global_limits
-            .active_agents
-            .fetch_sub(1, Ordering::Relaxed);
         self.dispatcher.remove_task_by_agent(name);
         self.try_remove_worktree(name);
     }
@@ -727,24 +1612,73 @@ impl OrchestratorRuntime {
         let cfg = WorktreeConfig {
             project_dir: PathBuf::from(&self.working_dir),
             agent_name: bus_name.to_string(),
-            target_branch: "master".to_string(), // unused for removal
+            target_branch: self.settings.default_branch.clone(), // unused for removal
         };
-        if let Err(e) = worktree::remove_worktree(&cfg) {
+        if let Err(e) = worktree::remove_worktree(&cfg, &self.settings.worktree) {
             tracing::warn!("Failed to remove worktree for {}: {}", bus_name, e);
         }
     }
 }
 
-async fn run_agent(factory: AgentFactory, config: AgentConfig, mailbox: agent_bus::Mailbox) {
+fn failure_comment(history: &[AttemptRecord]) -> String {
+    let mut comment = format!("Failed after {} attempts:", history.len());
+    for (n, attempt) in history.iter().enumerate() {
+        let reason = attempt
+            .outcome
+            .map_or("ended without a recorded reason", |o| o.describe());
+        comment.push_str(&format!("\n- attempt {}: {}", n + 1, reason));
+    }
+    comment
+}
+
+/// A new worktree's `post_create` command, run in the agent's sandbox
+/// before the agent starts.
+pub(crate) struct PostCreate {
+    command: String,
+    worktree: PathBuf,
+    prefix: Vec<String>,
+    timeout: Duration,
+}
+
+impl PostCreate {
+    /// Failures are logged; the agent still starts and can fix its
+    /// environment itself.
+    async fn run(self, agent_id: &AgentId) {
+        tracing::info!("Running post-create for {}: {}", agent_id, self.command);
+        let outcome =
+            support::run_check_command(&self.command, &self.worktree, &self.prefix, self.timeout)
+                .await;
+        if !outcome.passed {
+            tracing::warn!(
+                "Post-create command for {} failed: {}",
+                agent_id,
+                outcome.log
+            );
+        }
+    }
+}
+
+async fn run_agent(
+    factory: AgentFactory,
+    config: AgentConfig,
+    mailbox: agent_bus::Mailbox,
+    slot: Option<AgentSlot>,
+    post_create: Option<PostCreate>,
+) -> AgentExit {
+    let _slot = slot;
     let agent_id = config.agent_id.clone();
+    if let Some(post_create) = post_create {
+        post_create.run(&agent_id).await;
+    }
     let agent = match factory(config, mailbox) {
         Ok(agent) => agent,
         Err(error) => {
             tracing::error!("Agent {} init failed: {}", agent_id, error);
-            return;
+            return AgentExit::Stopped;
         }
     };
-    if let Err(error) = agent.run().await {
+    agent.run().await.unwrap_or_else(|error| {
         tracing::error!("Agent {} error: {}", agent_id, error);
-    }
+        AgentExit::Stopped
+    })
 }
@@ -0,0 +1,152 @@
+//! Shared build caches for Rust task worktrees.
+//!
+//! `shared-target` gives each live task agent one of a pool of target dirs
+//! under the project's data dir, reusing the lowest index no live agent
+//! holds, so builds start warm without agents queueing on one cargo lock.
+//! `sccache` wraps rustc with a cache shared by every project. Cache dirs are
+//! bind-mounted writable at their host path, so the injected environment is
+//! valid inside the sandbox; without one it is set through an `env` prefix.
+//! `reflink` is applied when a worktree is created.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::OrchestratorRuntime;
+use crate::config::BuildCache;
+
+/// Which shared target dir each task agent builds into.
+#[derive(Default)]
+pub(crate) struct BuildSlots(Mutex<HashMap<String, usize>>);
+
+impl BuildSlots {
+    /// The agent's current slot, or the lowest one no live agent holds.
+    fn acquire(&self, agent: &str, is_live: impl Fn(&str) -> bool) -> usize {
+        let mut slots = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        slots.retain(|name, _| name == agent || is_live(name));
+        if let Some(&slot) = slots.get(agent) {
+            return slot;
+        }
+        let slot = (0..)
+            .find(|n| !slots.values().any(|held| held == n))
+            .unwrap_or_default();
+        slots.insert(agent.to_string(), slot);
+        slot
+    }
+}
+
+/// Sandbox mounts and environment for a build cache.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct CacheExtras {
+    pub mounts: Vec<(String, String)>,
+    pub env: Vec<(String, String)>,
+}
+
+impl OrchestratorRuntime {
+    pub(crate) fn build_cache_extras(&self, bus_name: &str) -> CacheExtras {
+        let dir = match self.settings.worktree.build_cache {
+            BuildCache::None | BuildCache::Reflink => return CacheExtras::default(),
+            BuildCache::SharedTarget => {
+                // A released agent may still be building until it exits.
+                let slot = self.build_slots.acquire(bus_name, |name| {
+                    self.agent_handles
+                        .get(name)
+                        .is_some_and(|handle| !handle.is_finished())
+                        || self
+                            .draining
+                            .iter()
+                            .any(|agent| agent.name == name && !agent.handle.is_finished())
+                });
+                data_dir()
+                    .join(&self.project)
+                    .join(format!("target-{slot}"))
+            }
+            BuildCache::Sccache if !sccache_available() => {
+                tracing::warn!("build_cache = \"sccache\" but sccache is not installed");
+                return CacheExtras::default();
+            }
+            BuildCache::Sccache => data_dir().join("sccache"),
+        };
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            tracing::warn!("Failed to create build cache {}: {}", dir.display(), e);
+            return CacheExtras::default();
+        }
+        cache_extras(self.settings.worktree.build_cache, &dir)
+    }
+}
+
+fn cache_extras(strategy: BuildCache, dir: &Path) -> CacheExtras {
+    let path = dir.to_string_lossy().into_owned();
+    let env = match strategy {
+        BuildCache::SharedTarget => vec![("CARGO_TARGET_DIR".to_string(), path.clone())],
+        BuildCache::Sccache => vec![
+            ("RUSTC_WRAPPER".to_string(), "sccache".to_string()),
+            ("SCCACHE_DIR".to_string(), path.clone()),
+        ],
+        BuildCache::None | BuildCache::Reflink => return CacheExtras::default(),
+    };
+    CacheExtras {
+        mounts: vec![(path.clone(), path)],
+        env,
+    }
+}
+
+fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("agent-orchestrator")
+}
+
+fn sccache_available() -> bool {
+    std::process::Command::new("sccache")
+        .arg("--version")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_are_reused_once_their_agent_is_gone() {
+        let slots = BuildSlots::default();
+        let all_live = |_: &str| true;
+
+        assert_eq!(slots.acquire("task-a", all_live), 0);
+        assert_eq!(slots.acquire("task-b", all_live), 1);
+        assert_eq!(slots.acquire("task-a", all_live), 0, "agent keeps its slot");
+
+        let only_b = |name: &str| name == "task-b";
+        assert_eq!(slots.acquire("task-c", only_b), 0);
+        assert_eq!(slots.acquire("task-d", all_live), 2);
+    }
+
+    #[test]
+    fn cache_dirs_are_mounted_at_the_same_path() {
+        let dir = Path::new("/data/cache/target-1");
+
+        let shared = cache_extras(BuildCache::SharedTarget, dir);
+        assert_eq!(
+            shared.mounts,
+            vec![(dir.display().to_string(), dir.display().to_string())]
+        );
+        assert_eq!(
+            shared.env,
+            vec![("CARGO_TARGET_DIR".to_string(), dir.display().to_string())]
+        );
+
+        let sccache = cache_extras(BuildCache::Sccache, dir);
+        assert!(
+            sccache
+                .env
+                .contains(&("RUSTC_WRAPPER".to_string(), "sccache".to_string()))
+        );
+        assert_eq!(
+            cache_extras(BuildCache::Reflink, dir),
+            CacheExtras::default()
+        );
+    }
+}
@@ -0,0 +1,425 @@
+//! Global and per-project agent concurrency limits.
+//!
+//! Every runtime reports its ready-task demand when it polls for dispatch.
+//! Slots are granted by weighted fair share: each project with demand is
+//! entitled to `max_concurrent * priority / total_priority` slots, and
+//! slots another project can't use are lent out instead of left idle.
+//! Paused projects, and every project while the daemon drains, get no slots.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Fair-share weight for projects without a configured priority.
+pub const DEFAULT_PROJECT_PRIORITY: u32 = 1;
+
+/// Global concurrency limits shared across all project runtimes.
+pub struct GlobalLimits {
+    pub max_concurrent: AtomicUsize,
+    pub active_agents: AtomicUsize,
+    /// Set once the daemon should finish in-flight work and exit.
+    draining: AtomicBool,
+    projects: Mutex<HashMap<String, ProjectQuota>>,
+}
+
+/// Per-project limit, weight and live counters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProjectQuota {
+    /// Per-project cap (None = only the global limit applies).
+    pub max: Option<usize>,
+    /// Fair-share weight relative to other projects.
+    pub priority: u32,
+    /// Task agents currently running.
+    pub active: usize,
+    /// Ready tasks reported at the project's last dispatch poll.
+    pub waiting: usize,
+    /// Dispatching stopped by the operator; running agents continue.
+    pub paused: bool,
+    /// Reviews, merge checks and merges the runtime still has in flight.
+    pub background: usize,
+}
+
+impl Default for ProjectQuota {
+    fn default() -> Self {
+        Self {
+            max: None,
+            priority: DEFAULT_PROJECT_PRIORITY,
+            active: 0,
+            waiting: 0,
+            paused: false,
+            background: 0,
+        }
+    }
+}
+
+impl GlobalLimits {
+    pub fn new(max: usize) -> Self {
+        Self {
+            max_concurrent: AtomicUsize::new(max),
+            active_agents: AtomicUsize::new(0),
+            draining: AtomicBool::new(false),
+            projects: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How many more agents can be spawned globally.
+    pub fn available_slots(&self) -> usize {
+        let max = self.max_concurrent.load(Ordering::Relaxed);
+        let active = self.active_agents.load(Ordering::Relaxed);
+        max.saturating_sub(active)
+    }
+
+    /// Apply a project's configured limit and weight (called when its runtime starts).
+    pub fn configure_project(&self, project: &str, max: Option<usize>, priority: u32) {
+        let mut projects = self.projects.lock().unwrap();
+        let quota = projects.entry(project.to_string()).or_default();
+        quota.max = max;
+        quota.priority = priority.max(1);
+    }
+
+    /// Override a project's limit, and optionally its weight, at runtime.
+    pub fn set_project_quota(&self, project: &str, max: Option<usize>, priority: Option<u32>) {
+        let mut projects = self.projects.lock().unwrap();
+        let quota = projects.entry(project.to_string()).or_default();
+        quota.max = max;
+        if let Some(priority) = priority {
+            quota.priority = priority.max(1);
+        }
+    }
+
+    pub fn set_paused(&self, project: &str, paused: bool) {
+        let mut projects = self.projects.lock().unwrap();
+        let quota = projects.entry(project.to_string()).or_default();
+        quota.paused = paused;
+        if paused {
+            quota.waiting = 0;
+        }
+    }
+
+    pub fn is_paused(&self, project: &str) -> bool {
+        self.projects
+            .lock()
+            .unwrap()
+            .get(project)
+            .is_some_and(|q| q.paused)
+    }
+
+    /// Stop dispatching everywhere so the daemon can exit once agents finish.
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    /// Whether `project` may not start new work right now.
+    pub fn dispatch_blocked(&self, project: &str) -> bool {
+        self.is_draining() || self.is_paused(project)
+    }
+
+    pub fn project_quota(&self, project: &str) -> Option<ProjectQuota> {
+        self.projects.lock().unwrap().get(project).cloned()
+    }
+
+    /// Take a slot for a task agent in `project`. The slot is released when
+    /// the returned guard is dropped, so it must live as long as the agent task.
+    pub fn acquire_slot(self: &Arc<Self>, project: &str) -> AgentSlot {
+        self.agent_started(project);
+        AgentSlot {
+            limits: self.clone(),
+            project: project.to_string(),
+        }
+    }
+
+    /// Correct `project`'s active count to the number of agents actually alive.
+    /// Returns the previous count when it was wrong.
+    pub fn reconcile_project(&self, project: &str, live: usize) -> Option<usize> {
+        let mut projects = self.projects.lock().unwrap();
+        let quota = projects.entry(project.to_string()).or_default();
+        let counted = quota.active;
+        if counted == live {
+            return None;
+        }
+        quota.active = live;
+        let _ = self
+            .active_agents
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                Some((n + live).saturating_sub(counted))
+            });
+        Some(counted)
+    }
+
+    fn agent_started(&self, project: &str) {
+        self.active_agents.fetch_add(1, Ordering::Relaxed);
+        let mut projects = self.projects.lock().unwrap();
+        projects.entry(project.to_string()).or_default().active += 1;
+    }
+
+    fn agent_stopped(&self, project: &str) {
+        let _ = self
+            .active_agents
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                Some(n.saturating_sub(1))
+            });
+        let mut projects = self.projects.lock().unwrap();
+        if let Some(quota) = projects.get_mut(project) {
+            quota.active = quota.active.saturating_sub(1);
+        }
+    }
+
+    /// Report `wanted` ready tasks for `project` and return how many may be spawned now.
+    pub fn request_slots(&self, project: &str, wanted: usize) -> usize {
+        let draining = self.is_draining();
+        let mut projects = self.projects.lock().unwrap();
+        let quota = projects.entry(project.to_string()).or_default();
+        if quota.paused || draining {
+            quota.waiting = 0;
+            return 0;
+        }
+        quota.waiting = wanted;
+        fair_share_grant(
+            &projects,
+            project,
+            self.max_concurrent.load(Ordering::Relaxed),
+            self.active_agents.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Forget a project's outstanding demand (its runtime stopped polling).
+    pub fn clear_demand(&self, project: &str) {
+        if let Some(quota) = self.projects.lock().unwrap().get_mut(project) {
+            quota.waiting = 0;
+            quota.background = 0;
+        }
+    }
+
+    /// Record how much review and merge work `project` still has in flight.
+    pub fn report_background_work(&self, project: &str, count: usize) {
+        let mut projects = self.projects.lock().unwrap();
+        projects.entry(project.to_string()).or_default().background = count;
+    }
+
+    /// Whether any project still has reviews or merges in flight.
+    pub fn has_background_work(&self) -> bool {
+        self.projects
+            .lock()
+            .unwrap()
+            .values()
+            .any(|q| q.background > 0)
+    }
+}
+
+/// A task agent's claim on a global and per-project slot.
+pub struct AgentSlot {
+    limits: Arc<GlobalLimits>,
+    project: String,
+}
+
+impl Drop for AgentSlot {
+    fn drop(&mut self) {
+        self.limits.agent_stopped(&self.project);
+    }
+}
+
+/// Slots `project` may take now without eating into the unused fair share
+/// of other projects that are still waiting for work to be dispatched.
+fn fair_share_grant(
+    quotas: &HashMap<String, ProjectQuota>,
+    project: &str,
+    global_max: usize,
+    global_active: usize,
+) -> usize {
+    let Some(own) = quotas.get(project) else {
+        return 0;
+    };
+    let free = global_max.saturating_sub(global_active);
+    let wanted = own.waiting.min(headroom(own)).min(free);
+    if wanted == 0 {
+        return 0;
+    }
+
+    let total_weight: u64 = quotas
+        .values()
+        .filter(|q| q.active > 0 || q.waiting > 0)
+        .map(|q| u64::from(q.priority))
+        .sum();
+    let reserved: usize = quotas
+        .iter()
+        .filter(|(name, q)| name.as_str() != project && q.waiting > 0)
+        .map(|(_, q)| {
+            let unused_share = fair_share(q, global_max, total_weight).saturating_sub(q.active);
+            unused_share.min(q.waiting).min(headroom(q))
+        })
+        .sum();
+    wanted.min(free.saturating_sub(reserved))
+}
+
+fn headroom(quota: &ProjectQuota) -> usize {
+    quota
+        .max
+        .map_or(usize::MAX, |max| max.saturating_sub(quota.active))
+}
+
+/// Entitled slots for a project with demand (at least one, so low weights never starve).
+fn fair_share(quota: &ProjectQuota, global_max: usize, total_weight: u64) -> usize {
+    if total_weight == 0 {
+        return global_max;
+    }
+    let share = global_max as u64 * u64::from(quota.priority) / total_weight;
+    (share as usize).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota(max: Option<usize>, priority: u32, active: usize, waiting: usize) -> ProjectQuota {
+        ProjectQuota {
+            max,
+            priority,
+            active,
+            waiting,
+            paused: false,
+            background: 0,
+        }
+    }
+
+    fn quotas(entries: &[(&str, ProjectQuota)]) -> HashMap<String, ProjectQuota> {
+        entries
+            .iter()
+            .map(|(name, q)| (name.to_string(), q.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn busy_project_leaves_room_for_others_with_demand() {
+        let quotas = quotas(&[
+            ("big", quota(None, 1, 0, 30)),
+            ("small", quota(None, 1, 0, 3)),
+        ]);
+
+        assert_eq!(fair_share_grant(&quotas, "big", 10, 0), 7);
+        assert_eq!(fair_share_grant(&quotas, "small", 10, 0), 3);
+    }
+
+    #[test]
+    fn idle_projects_do_not_reserve_slots() {
+        let quotas = quotas(&[
+            ("big", quota(None, 1, 0, 30)),
+            ("idle", quota(None, 1, 0, 0)),
+        ]);
+
+        assert_eq!(fair_share_grant(&quotas, "big", 10, 0), 10);
+    }
+
+    #[test]
+    fn priority_weights_the_share() {
+        let quotas = quotas(&[
+            ("important", quota(None, 3, 0, 30)),
+            ("other", quota(None, 1, 0, 30)),
+        ]);
+
+        assert_eq!(fair_share_grant(&quotas, "important", 8, 0), 6);
+        assert_eq!(fair_share_grant(&quotas, "other", 8, 0), 2);
+    }
+
+    #[test]
+    fn project_over_its_share_waits_for_the_other() {
+        let quotas = quotas(&[
+            ("big", quota(None, 1, 7, 20)),
+            ("small", quota(None, 1, 0, 5)),
+        ]);
+
+        assert_eq!(fair_share_grant(&quotas, "big", 10, 7), 0);
+        assert_eq!(fair_share_grant(&quotas, "small", 10, 7), 3);
+    }
+
+    #[test]
+    fn per_project_max_caps_grant_and_reservation() {
+        let quotas = quotas(&[
+            ("capped", quota(Some(2), 1, 1, 10)),
+            ("other", quota(None, 1, 0, 10)),
+        ]);
+
+        assert_eq!(fair_share_grant(&quotas, "capped", 10, 1), 1);
+        // "capped" can only use one more slot, so "other" may take the rest.
+        assert_eq!(fair_share_grant(&quotas, "other", 10, 1), 8);
+    }
+
+    #[test]
+    fn counters_follow_slot_guards() {
+        let limits = Arc::new(GlobalLimits::new(4));
+        limits.configure_project("p", Some(2), 1);
+
+        assert_eq!(limits.request_slots("p", 5), 2);
+        let first = limits.acquire_slot("p");
+        let second = limits.acquire_slot("p");
+        assert_eq!(limits.request_slots("p", 3), 0);
+
+        drop(first);
+        assert_eq!(limits.available_slots(), 3);
+        assert_eq!(limits.request_slots("p", 3), 1);
+
+        limits.set_project_quota("p", None, Some(2));
+        assert_eq!(limits.request_slots("p", 3), 3);
+        assert_eq!(limits.project_quota("p").map(|q| q.priority), Some(2));
+
+        drop(second);
+        assert_eq!(limits.available_slots(), 4);
+    }
+
+    #[test]
+    fn paused_and_draining_projects_get_no_slots() {
+        let limits = GlobalLimits::new(4);
+        limits.set_paused("p", true);
+
+        assert_eq!(limits.request_slots("p", 3), 0);
+        assert_eq!(limits.project_quota("p").map(|q| q.waiting), Some(0));
+        assert_eq!(limits.request_slots("other", 3), 3);
+
+        limits.set_paused("p", false);
+        assert_eq!(limits.request_slots("p", 1), 1);
+
+        limits.start_draining();
+        assert!(limits.dispatch_blocked("other"));
+        assert_eq!(limits.request_slots("other", 3), 0);
+    }
+
+    #[test]
+    fn background_work_is_tracked_until_the_runtime_stops() {
+        let limits = GlobalLimits::new(4);
+        assert!(!limits.has_background_work());
+
+        limits.report_background_work("p", 2);
+        assert!(limits.has_background_work());
+
+        limits.clear_demand("p");
+        assert!(!limits.has_background_work());
+    }
+
+    #[test]
+    fn stop_without_start_saturates() {
+        let limits = Arc::new(GlobalLimits::new(4));
+        limits.agent_stopped("p");
+
+        assert_eq!(limits.active_agents.load(Ordering::Relaxed), 0);
+        assert_eq!(limits.available_slots(), 4);
+    }
+
+    #[test]
+    fn reconcile_corrects_leaked_counts() {
+        let limits = Arc::new(GlobalLimits::new(10));
+        let other = limits.acquire_slot("other");
+        let leaked = limits.acquire_slot("p");
+        std::mem::forget(leaked);
+        let _live = limits.acquire_slot("p");
+
+        assert_eq!(limits.reconcile_project("p", 1), Some(2));
+        assert_eq!(limits.reconcile_project("p", 1), None);
+        assert_eq!(limits.project_quota("p").map(|q| q.active), Some(1));
+        assert_eq!(limits.available_slots(), 8);
+        drop(other);
+        assert_eq!(limits.available_slots(), 9);
+    }
+}
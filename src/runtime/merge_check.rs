@@ -0,0 +1,195 @@
+//! Post-merge checks of natively merged task branches.
+//!
+//! When a project sets `test_command`, a conflict-free task branch is first
+//! merged in a scratch worktree and the command runs there, inside the task
+//! sandbox, from a background task. The result comes back as
+//! `merge_check_result`. Only a passing merge is fast-forwarded onto the
+//! target branch in the project checkout; a failing one marks the task
+//! merge_conflict, and one that conflicts or can no longer be fast-forwarded
+//! goes to the merger.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::{OrchestratorRuntime, PendingMerge};
+use crate::config::WorktreeSetup;
+use crate::runtime_support as support;
+use crate::types::AgentRole;
+use crate::worktree::{self, NativeMerge, ScratchMerge};
+
+/// How long the post-merge test command may run before the merge is dropped.
+const MERGE_CHECK_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Everything the background check needs, detached from the runtime.
+#[derive(Clone)]
+struct MergeCheckJob {
+    project_dir: PathBuf,
+    scratch: PathBuf,
+    branch: String,
+    target: String,
+    setup: WorktreeSetup,
+    command: String,
+    prefix: Vec<String>,
+}
+
+impl OrchestratorRuntime {
+    /// Merge and check `merge` in the background; the result comes back as
+    /// `merge_check_result`. Returns false if the project has no test command.
+    pub(super) fn start_merge_check(&mut self, merge: &PendingMerge, target: &str) -> bool {
+        let Some(command) = self.settings.test_command.clone() else {
+            return false;
+        };
+        let project_dir = PathBuf::from(&self.working_dir);
+        let scratch = project_dir
+            .join(".worktrees")
+            .join(format!("merge-check-{}", merge.agent_name));
+        let use_sandbox = self.settings.sandbox && llm_sdk::sandbox::is_available();
+        let (_, prefix) = support::resolve_sandbox_with(
+            AgentRole::TaskAgent,
+            &project_dir,
+            Ok(scratch.clone()),
+            use_sandbox,
+            &self.sandbox_extras(&merge.agent_name),
+        );
+        tracing::info!(
+            "Checking merge of {} into {} with `{}`",
+            merge.branch(),
+            target,
+            command
+        );
+        self.merge_checks
+            .insert(merge.task_id.clone(), merge.clone());
+
+        let job = MergeCheckJob {
+            project_dir,
+            scratch,
+            branch: merge.branch(),
+            target: target.to_string(),
+            setup: self.settings.worktree.clone(),
+            command,
+            prefix,
+        };
+        let bus = self.bus.clone();
+        let mut payload = serde_json::json!({
+            "task_id": merge.task_id,
+            "target_branch": target,
+        });
+        tokio::spawn(async move {
+            let (result, commit, log) = run_merge_check(job).await;
+            payload["result"] = result.into();
+            payload["commit"] = commit.into();
+            payload["log"] = log.into();
+            let name = format!("merge-check-{}", support::payload_str(&payload, "task_id"));
+            match bus.register(&name) {
+                Ok(mailbox) => {
+                    let _ = mailbox.send("runtime", "merge_check_result", payload);
+                }
+                Err(e) => tracing::warn!("Failed to report merge check result: {}", e),
+            }
+            bus.deregister(&name);
+        });
+        true
+    }
+
+    /// Land a checked merge, record its failure, or hand it to the merger.
+    pub(super) async fn handle_merge_check_result(&mut self, payload: &serde_json::Value) {
+        let task_id = support::payload_str(payload, "task_id");
+        let target = support::payload_str(payload, "target_branch");
+        let Some(merge) = self.merge_checks.remove(&task_id) else {
+            tracing::info!("Dropping merge check result for {task_id}: no longer pending");
+            return;
+        };
+        match support::payload_str(payload, "result").as_str() {
+            "passed" => {
+                let commit = support::payload_str(payload, "commit");
+                // The merger may be moving the target branch; let it finish.
+                let landed = self.pending_merges.is_empty()
+                    && self.fast_forward_checked(&merge, &target, &commit).await;
+                if !landed {
+                    self.send_merge_request(merge, &target);
+                }
+            }
+            "failed" => {
+                let command = self.settings.test_command.clone().unwrap_or_default();
+                let log = support::payload_str(payload, "log");
+                let report = format!(
+                    "Post-merge check `{command}` failed, {target} was left unchanged.\n\n```\n{log}\n```"
+                );
+                self.mark_merge_conflict(&merge, &report).await;
+            }
+            _ => {
+                tracing::info!(
+                    "Task {} could not be merged natively ({}), using merger agent",
+                    task_id,
+                    support::payload_str(payload, "log")
+                );
+                self.send_merge_request(merge, &target);
+            }
+        }
+    }
+
+    /// Fast-forward the target branch to a merge whose check passed.
+    async fn fast_forward_checked(&self, merge: &PendingMerge, target: &str, commit: &str) -> bool {
+        let (dir, target_branch, commit) = (
+            PathBuf::from(&self.working_dir),
+            target.to_string(),
+            commit.to_string(),
+        );
+        let outcome = tokio::task::spawn_blocking(move || {
+            worktree::fast_forward_target(&dir, &target_branch, &commit)
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+        match outcome {
+            Ok(NativeMerge::Merged) => {
+                let summary = format!(
+                    "Merged {} into {target} without conflicts; post-merge check passed",
+                    merge.branch()
+                );
+                self.finish_merged_task(merge, &summary).await;
+                true
+            }
+            Ok(NativeMerge::Conflicts | NativeMerge::Unavailable(_)) | Err(_) => {
+                tracing::info!(
+                    "Checked merge of {} can't be fast-forwarded onto {}",
+                    merge.task_id,
+                    target
+                );
+                false
+            }
+        }
+    }
+}
+
+/// Merge in the scratch worktree and run the check there. Returns the
+/// result (`passed`, `failed` or `unmerged`), the merge commit and the log.
+async fn run_merge_check(job: MergeCheckJob) -> (&'static str, String, String) {
+    let prepare = job.clone();
+    let merged = tokio::task::spawn_blocking(move || {
+        worktree::merge_in_scratch_worktree(
+            &prepare.project_dir,
+            &prepare.scratch,
+            &prepare.branch,
+            &prepare.target,
+            &prepare.setup,
+        )
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    let commit = match merged {
+        Ok(ScratchMerge::Merged { commit }) => commit,
+        Ok(ScratchMerge::Conflicts) => return ("unmerged", String::new(), "conflicts".into()),
+        Err(e) => return ("unmerged", String::new(), format!("{e:#}")),
+    };
+
+    let outcome =
+        support::run_check_command(&job.command, &job.scratch, &job.prefix, MERGE_CHECK_TIMEOUT)
+            .await;
+    let (dir, scratch) = (job.project_dir, job.scratch);
+    let _ = tokio::task::spawn_blocking(move || worktree::remove_scratch_worktree(&dir, &scratch))
+        .await;
+    let result = if outcome.passed { "passed" } else { "failed" };
+    (result, commit, outcome.log)
+}
@@ -1,20 +1,173 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
 use llm_tasks::db::Event;
+use serde::{Deserialize, Serialize};
+
+const INTERNAL_RETRY_RESET_ACTORS: &[&str] =
+    &["runtime", "watchdog", "architect", "reviewer", "merger"];
+/// Longest wait between two attempts of the same task.
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60 * 60);
 
-const INTERNAL_RETRY_RESET_ACTORS: &[&str] = &["runtime", "architect", "reviewer", "merger"];
+/// Why an earlier attempt at a task ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum AttemptOutcome {
+    /// The agent went idle and the watchdog reclaimed the task.
+    Timeout,
+    /// The agent exited without reporting, or the runtime lost it.
+    Crash,
+    /// The agent asked for more information.
+    Blocked,
+    /// The reviewer sent the work back.
+    ReviewRejected,
+}
+
+impl AttemptOutcome {
+    pub(super) fn describe(self) -> &'static str {
+        match self {
+            AttemptOutcome::Timeout => "timed out",
+            AttemptOutcome::Crash => "agent crashed",
+            AttemptOutcome::Blocked => "blocked (needs info)",
+            AttemptOutcome::ReviewRejected => "rejected in review",
+        }
+    }
+}
+
+/// One dispatch of a task since the last manual reset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct AttemptRecord {
+    /// None while the attempt is running or when it ended some other way.
+    pub(super) outcome: Option<AttemptOutcome>,
+    /// Timestamp of the event that ended the attempt.
+    pub(super) ended_at: Option<String>,
+}
 
-pub(super) fn count_attempts_since_manual_reset(events: &[Event]) -> u32 {
+/// Attempts since the last manual reset, oldest first, with how each ended.
+pub(super) fn attempts_since_manual_reset(events: &[Event]) -> Vec<AttemptRecord> {
     let reset_idx = events.iter().rposition(is_manual_retry_reset);
+    let events = &events[reset_idx.map_or(0, |idx| idx + 1)..];
+    let claims: Vec<usize> = events
+        .iter()
+        .enumerate()
+        .filter(|(_, event)| event.action == "claimed")
+        .map(|(idx, _)| idx)
+        .collect();
+    claims
+        .iter()
+        .enumerate()
+        .map(|(n, &start)| {
+            let end = claims.get(n + 1).copied().unwrap_or(events.len());
+            attempt_record(&events[start + 1..end])
+        })
+        .collect()
+}
+
+fn attempt_record(events: &[Event]) -> AttemptRecord {
     events
         .iter()
-        .skip(reset_idx.map_or(0, |idx| idx + 1))
-        .filter(|event| event.action == "claimed")
-        .count() as u32
+        .filter(|event| event.action == "updated" && event.field.as_deref() == Some("status"))
+        .find_map(|event| {
+            attempt_outcome(event).map(|outcome| AttemptRecord {
+                outcome: Some(outcome),
+                ended_at: Some(event.timestamp.clone()),
+            })
+        })
+        .unwrap_or(AttemptRecord {
+            outcome: None,
+            ended_at: None,
+        })
+}
+
+fn attempt_outcome(status_event: &Event) -> Option<AttemptOutcome> {
+    match (
+        status_event.new_value.as_deref()?,
+        status_event.actor.as_str(),
+    ) {
+        ("needs_info", _) => Some(AttemptOutcome::Blocked),
+        ("ready" | "pending", "reviewer") => Some(AttemptOutcome::ReviewRejected),
+        ("ready" | "pending", "watchdog") => Some(AttemptOutcome::Timeout),
+        ("ready" | "pending", "runtime") => Some(AttemptOutcome::Crash),
+        _ => None,
+    }
+}
+
+/// Wait before attempt `finished + 1`: `base` doubled per finished attempt, capped.
+pub(super) fn retry_backoff(base: Duration, finished: u32) -> Duration {
+    if finished == 0 || base.is_zero() {
+        return Duration::ZERO;
+    }
+    let exponent = (finished - 1).min(16);
+    base.saturating_mul(1 << exponent).min(MAX_RETRY_BACKOFF)
+}
+
+/// Per-task attempt limits set by the operator, in a sidecar file
+/// (`attempt_limits.json`, next to tasks.db) that only the orchestrator
+/// writes, so task comments can't change a task's budget.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AttemptLimits {
+    #[serde(default)]
+    pub tasks: BTreeMap<String, u32>,
+}
+
+/// Where the attempt limits of the project whose database is `db_path` live.
+pub fn attempt_limits_path(db_path: &Path) -> PathBuf {
+    db_path.with_file_name("attempt_limits.json")
+}
+
+impl AttemptLimits {
+    /// Load the saved limits, starting empty if the file is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        Self::load_from(path).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring attempt limits {}: {e:#}", path.display());
+            Self::default()
+        })
+    }
+
+    fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+        Ok(())
+    }
+
+    /// The task's limit, or `default` when none was set.
+    pub fn max_attempts(&self, task_id: &str, default: u32) -> u32 {
+        self.tasks.get(task_id).copied().unwrap_or(default)
+    }
+}
+
+/// Record `max_attempts` as the attempt limit of `task_id`.
+pub fn set_max_attempts(path: &Path, task_id: &str, max_attempts: u32) -> Result<()> {
+    let mut limits = AttemptLimits::load_from(path)?;
+    limits.tasks.insert(task_id.to_string(), max_attempts);
+    limits.save_to(path)
 }
 
+/// A person putting the task back in the queue. Answering a needs_info task
+/// continues the same run, so it keeps the attempt history.
 fn is_manual_retry_reset(event: &Event) -> bool {
     event.action == "updated"
         && event.field.as_deref() == Some("status")
         && matches!(event.new_value.as_deref(), Some("pending" | "ready"))
+        && event.old_value.as_deref() != Some("needs_info")
         && !is_internal_retry_reset_actor(&event.actor)
 }
 
@@ -24,8 +177,7 @@ fn is_internal_retry_reset_actor(actor: &str) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::count_attempts_since_manual_reset;
-    use llm_tasks::db::Event;
+    use super::*;
 
     fn event(
         id: i64,
@@ -100,7 +252,7 @@ mod tests {
             ),
         ];
 
-        assert_eq!(count_attempts_since_manual_reset(&events), 1);
+        assert_eq!(attempts_since_manual_reset(&events).len(), 1);
     }
 
     #[test]
@@ -132,6 +284,105 @@ mod tests {
             ),
         ];
 
-        assert_eq!(count_attempts_since_manual_reset(&events), 2);
+        assert_eq!(attempts_since_manual_reset(&events).len(), 2);
+    }
+
+    fn claimed(id: i64) -> Event {
+        event(
+            id,
+            "task-lt-test",
+            "claimed",
+            Some("assignee"),
+            None,
+            Some("task-lt-test"),
+        )
+    }
+
+    fn status(id: i64, actor: &str, from: &str, to: &str) -> Event {
+        event(id, actor, "updated", Some("status"), Some(from), Some(to))
+    }
+
+    #[test]
+    fn attempts_record_why_each_ended() {
+        let events = vec![
+            claimed(1),
+            status(2, "watchdog", "in_progress", "ready"),
+            claimed(3),
+            status(4, "runtime", "in_progress", "needs_info"),
+            claimed(5),
+            status(6, "runtime", "in_progress", "in_review"),
+            status(7, "reviewer", "in_review", "ready"),
+            claimed(8),
+            status(9, "runtime", "in_progress", "ready"),
+            claimed(10),
+        ];
+
+        let outcomes: Vec<Option<AttemptOutcome>> = attempts_since_manual_reset(&events)
+            .into_iter()
+            .map(|a| a.outcome)
+            .collect();
+
+        assert_eq!(
+            outcomes,
+            vec![
+                Some(AttemptOutcome::Timeout),
+                Some(AttemptOutcome::Blocked),
+                Some(AttemptOutcome::ReviewRejected),
+                Some(AttemptOutcome::Crash),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn watchdog_reclaim_does_not_reset_attempt_budget() {
+        let events = vec![
+            claimed(1),
+            status(2, "watchdog", "in_progress", "ready"),
+            claimed(3),
+        ];
+
+        assert_eq!(attempts_since_manual_reset(&events).len(), 2);
+    }
+
+    #[test]
+    fn answering_needs_info_does_not_reset_attempt_budget() {
+        let events = vec![
+            claimed(1),
+            status(2, "runtime", "in_progress", "needs_info"),
+            status(3, "user", "needs_info", "ready"),
+            claimed(4),
+        ];
+
+        let attempts = attempts_since_manual_reset(&events);
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0].outcome, Some(AttemptOutcome::Blocked));
+    }
+
+    #[test]
+    fn retry_backoff_doubles_per_finished_attempt() {
+        let base = Duration::from_secs(60);
+
+        assert_eq!(retry_backoff(base, 0), Duration::ZERO);
+        assert_eq!(retry_backoff(base, 1), Duration::from_secs(60));
+        assert_eq!(retry_backoff(base, 3), Duration::from_secs(240));
+        assert_eq!(retry_backoff(base, 30), MAX_RETRY_BACKOFF);
+        assert_eq!(retry_backoff(Duration::ZERO, 3), Duration::ZERO);
+    }
+
+    #[test]
+    fn attempt_limits_round_trip_and_missing_file_is_empty() {
+        let dir = std::env::temp_dir().join(format!("attempt-limits-{}", std::process::id()));
+        let path = attempt_limits_path(&dir.join("tasks.db"));
+
+        assert_eq!(AttemptLimits::load(&path), AttemptLimits::default());
+
+        set_max_attempts(&path, "lt-a", 5).unwrap();
+        set_max_attempts(&path, "lt-a", 2).unwrap();
+        let limits = AttemptLimits::load(&path);
+        assert_eq!(limits.max_attempts("lt-a", 3), 2);
+        assert_eq!(limits.max_attempts("lt-b", 3), 3);
+
+        std::fs::remove_dir_all(dir).ok();
     }
 }
@@ -0,0 +1,167 @@
+//! Review rejections sent back to the agent that did the work.
+//!
+//! A task agent stays alive after reporting completion while its work is in
+//! review. When the reviewer rejects it, the assessment goes back to the same
+//! session and worktree as `review_feedback`, and the task returns to
+//! in_progress under the same attempt. After `max_revisions` rejections, or
+//! once the agent is gone, the task is requeued for a fresh attempt instead.
+
+use super::OrchestratorRuntime;
+use crate::runtime_support as support;
+
+/// An agent kept alive while its completed task is reviewed.
+pub(super) struct ReviewHold {
+    agent_name: String,
+    attempt: u32,
+}
+
+impl OrchestratorRuntime {
+    /// Keep a completed task's agent for a possible revision. Returns false
+    /// when revisions are disabled and the caller should release the agent.
+    pub(super) fn hold_for_review(
+        &mut self,
+        task_id: &str,
+        agent_name: &str,
+        attempt: u32,
+    ) -> bool {
+        if self.settings.max_revisions == 0 || !self.is_agent_live(agent_name) {
+            return false;
+        }
+        self.review_holds.insert(
+            task_id.to_string(),
+            ReviewHold {
+                agent_name: agent_name.to_string(),
+                attempt,
+            },
+        );
+        true
+    }
+
+    /// Release the agent held for a task whose review is over.
+    pub(super) fn release_review_hold(&mut self, task_id: &str) {
+        self.revisions.remove(task_id);
+        if let Some(hold) = self.review_holds.remove(task_id) {
+            self.release_agent(&hold.agent_name);
+        }
+    }
+
+    /// Send the reviewer's assessment back to the agent that did the work,
+    /// or requeue the task when it has no revisions left.
+    pub(super) async fn handle_review_rejected(&mut self, payload: &serde_json::Value) {
+        let task_id = support::payload_str(payload, "task_id");
+        let assessment = support::payload_str(payload, "content");
+        let revision = self.revisions.get(&task_id).copied().unwrap_or(0) + 1;
+        let hold = match self.review_holds.remove(&task_id) {
+            Some(hold)
+                if revision <= self.settings.max_revisions
+                    && self.is_agent_live(&hold.agent_name) =>
+            {
+                hold
+            }
+            other => {
+                if let Some(hold) = other {
+                    self.release_agent(&hold.agent_name);
+                }
+                self.revisions.remove(&task_id);
+                self.requeue_rejected(&task_id).await;
+                return;
+            }
+        };
+
+        self.dispatcher
+            .register_active(task_id.clone(), hold.agent_name.clone(), hold.attempt);
+        let updates = llm_tasks::db::TaskUpdates {
+            status: Some("in_progress"),
+            ..Default::default()
+        };
+        if let Err(e) = self.db.update_task(&task_id, updates, "reviewer").await {
+            tracing::error!("Failed to reopen task {} for revision: {}", task_id, e);
+        }
+        let message = review_feedback_prompt(&assessment, revision, self.settings.max_revisions);
+        let payload = serde_json::json!({"content": message, "task_id": task_id});
+        if let Err(e) = self
+            .dispatcher
+            .notify(&hold.agent_name, "review_feedback", payload)
+        {
+            tracing::warn!(
+                "Failed to send review feedback to {}: {}",
+                hold.agent_name,
+                e
+            );
+            self.dispatcher.remove_task_by_agent(&hold.agent_name);
+            self.release_agent(&hold.agent_name);
+            self.revisions.remove(&task_id);
+            self.requeue_rejected(&task_id).await;
+            return;
+        }
+        tracing::info!(
+            "Task {} sent back to {} for revision {}/{}",
+            task_id,
+            hold.agent_name,
+            revision,
+            self.settings.max_revisions
+        );
+        self.revisions.insert(task_id, revision);
+    }
+
+    /// Release held agents whose task left review without a verdict reaching
+    /// the runtime (cancelled, deleted, or closed by hand).
+    pub(super) async fn release_stale_review_holds(&mut self) {
+        let held: Vec<String> = self.review_holds.keys().cloned().collect();
+        for task_id in held {
+            let in_review = matches!(
+                self.db.get_task(&task_id).await,
+                Ok(task) if task.status == "in_review"
+            );
+            if !in_review {
+                tracing::info!("Task {} left review, releasing its agent", task_id);
+                self.release_review_hold(&task_id);
+            }
+        }
+    }
+
+    /// Forget the review hold on `agent_name`, if there is one.
+    pub(super) fn drop_review_hold_of(&mut self, agent_name: &str) -> bool {
+        let before = self.review_holds.len();
+        self.review_holds
+            .retain(|_, hold| hold.agent_name != agent_name);
+        self.review_holds.len() != before
+    }
+
+    /// Put a rejected task back to ready for a fresh attempt.
+    async fn requeue_rejected(&self, task_id: &str) {
+        let updates = llm_tasks::db::TaskUpdates {
+            status: Some("ready"),
+            ..Default::default()
+        };
+        let _ = self.db.update_task(task_id, updates, "reviewer").await;
+        let _ = self.db.clear_assignee(task_id, "reviewer").await;
+        tracing::warn!("Task {task_id} completion rejected, requeued");
+    }
+
+    fn is_agent_live(&self, agent_name: &str) -> bool {
+        self.agent_handles
+            .get(agent_name)
+            .is_some_and(|handle| !handle.is_finished())
+    }
+}
+
+fn review_feedback_prompt(assessment: &str, revision: u32, max_revisions: u32) -> String {
+    format!(
+        "The reviewer rejected your work (revision {revision} of {max_revisions}). \
+         Address the feedback below in the same worktree, commit, and report \
+         completion again.\n\n{assessment}"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feedback_prompt_counts_revisions() {
+        let prompt = review_feedback_prompt("Tests for the parser are missing.", 1, 2);
+        assert!(prompt.contains("revision 1 of 2"));
+        assert!(prompt.ends_with("Tests for the parser are missing."));
+    }
+}
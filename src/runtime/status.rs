@@ -0,0 +1,66 @@
+//! Answers `status_request` from the control server with what each task
+//! agent is working on, plus task counts for the project.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use super::OrchestratorRuntime;
+use crate::control::{AgentStatus, RuntimeStatus};
+use crate::dispatch::AssignmentInfo;
+use crate::worktree::WorktreeConfig;
+
+impl OrchestratorRuntime {
+    pub(super) async fn reply_status(&self, to: &str) {
+        let status = self.runtime_status().await;
+        let payload = match serde_json::to_value(&status) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::warn!("Failed to serialize status: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = self.dispatcher.notify(to, "status_reply", payload) {
+            tracing::warn!("Failed to send status to {}: {}", to, e);
+        }
+    }
+
+    async fn runtime_status(&self) -> RuntimeStatus {
+        let mut task_counts = BTreeMap::new();
+        for task in self.db.list_tasks(None, None).await.unwrap_or_default() {
+            *task_counts.entry(task.status).or_default() += 1;
+        }
+        let mut agents = Vec::new();
+        for info in self.dispatcher.assignments() {
+            agents.push(self.assignment_status(info).await);
+        }
+        agents.sort_by(|a, b| a.name.cmp(&b.name));
+        RuntimeStatus {
+            agents,
+            task_counts,
+        }
+    }
+
+    async fn assignment_status(&self, info: AssignmentInfo) -> AgentStatus {
+        let task_title = self.db.get_task(&info.task_id).await.ok().map(|t| t.title);
+        let cfg = WorktreeConfig {
+            project_dir: PathBuf::from(&self.working_dir),
+            agent_name: info.agent_name.clone(),
+            target_branch: self.settings.default_branch.clone(),
+        };
+        let worktree = cfg.path();
+        let has_worktree = worktree.exists();
+        AgentStatus {
+            role: "task_agent".to_string(),
+            task_id: Some(info.task_id),
+            task_title,
+            claimed_secs: Some(info.since_claim.as_secs()),
+            idle_secs: Some(info.idle.as_secs()),
+            attempt: Some(info.attempt),
+            worktree: has_worktree.then(|| worktree.display().to_string()),
+            branch: has_worktree.then(|| cfg.branch()),
+            backend: Some(self.settings.backend.name().to_string()),
+            model: self.settings.backend.model().map(str::to_string),
+            name: info.agent_name,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! "Previous attempts and feedback" section of task assignments.
+//!
+//! Reviewer rejections, check reports, needs_info answers and user comments
+//! are stored as task comments. A retry gets them, with how each earlier
+//! attempt ended, so it can build on the feedback instead of repeating the
+//! same mistakes. The section is capped at `HISTORY_TOKEN_BUDGET`; when the
+//! comments don't fit, the oldest are left out.
+
+use llm_tasks::db::Event;
+
+use super::OrchestratorRuntime;
+use super::retry_budget::attempts_since_manual_reset;
+
+/// Rough size limit of the section, in tokens.
+const HISTORY_TOKEN_BUDGET: usize = 2000;
+const CHARS_PER_TOKEN: usize = 4;
+/// Longest single comment quoted in the section.
+const COMMENT_MAX_CHARS: usize = 1500;
+
+impl OrchestratorRuntime {
+    /// History section for the assignment of `task_id`, or None for a task
+    /// with no earlier attempts or comments.
+    pub(super) async fn task_history(&self, task_id: &str) -> Option<String> {
+        let events = self.db.get_events(task_id).await.unwrap_or_default();
+        let comments = self.db.get_comments(task_id).await.unwrap_or_default();
+        history_section(&events, comments.iter().map(|c| c.content.as_str()))
+    }
+}
+
+/// Build the section from the task's events and its comments, oldest first.
+/// The newest attempt in `events` is the one being assigned and is left out.
+fn history_section<'a>(
+    events: &[Event],
+    comments: impl IntoIterator<Item = &'a str>,
+) -> Option<String> {
+    let attempts = attempts_since_manual_reset(events);
+    let earlier = &attempts[..attempts.len().saturating_sub(1)];
+    let comments: Vec<&str> = comments
+        .into_iter()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .collect();
+    if earlier.is_empty() && comments.is_empty() {
+        return None;
+    }
+
+    let mut section = String::from("## Previous attempts and feedback\n");
+    if !earlier.is_empty() {
+        section.push_str("\nEarlier attempts at this task:\n");
+        for (n, attempt) in earlier.iter().enumerate() {
+            let outcome = attempt
+                .outcome
+                .map_or("ended without a recorded reason", |o| o.describe());
+            section.push_str(&format!("- Attempt {}: {}\n", n + 1, outcome));
+        }
+    }
+
+    let budget = (HISTORY_TOKEN_BUDGET * CHARS_PER_TOKEN).saturating_sub(section.len());
+    let mut used = 0;
+    let mut kept = Vec::new();
+    for text in comments.iter().rev() {
+        let entry = comment_entry(text);
+        if used + entry.len() > budget {
+            break;
+        }
+        used += entry.len();
+        kept.push(entry);
+    }
+    if !kept.is_empty() {
+        section.push_str("\nComments, oldest first:\n");
+        let omitted = comments.len() - kept.len();
+        if omitted > 0 {
+            section.push_str(&format!("- ({omitted} earlier comments omitted)\n"));
+        }
+        for entry in kept.iter().rev() {
+            section.push_str(entry);
+        }
+    }
+    Some(section)
+}
+
+/// One comment as a list item, continuation lines indented under it.
+fn comment_entry(text: &str) -> String {
+    let text = claude_architect::truncate(text, COMMENT_MAX_CHARS);
+    format!("- {}\n", text.replace('\n', "\n  "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: i64, actor: &str, action: &str, new_value: Option<&str>) -> Event {
+        let field = if action == "claimed" {
+            "assignee"
+        } else {
+            "status"
+        };
+        Event {
+            id,
+            task_id: "lt-test".to_string(),
+            actor: actor.to_string(),
+            action: action.to_string(),
+            field: Some(field.to_string()),
+            old_value: None,
+            new_value: new_value.map(str::to_string),
+            timestamp: "2026-03-12T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn first_attempt_without_comments_has_no_history() {
+        let events = vec![event(1, "task-lt-test", "claimed", Some("task-lt-test"))];
+        assert_eq!(history_section(&events, []), None);
+    }
+
+    #[test]
+    fn earlier_attempts_and_comments_are_listed() {
+        let events = vec![
+            event(1, "task-lt-test", "claimed", Some("task-lt-test")),
+            event(2, "reviewer", "updated", Some("ready")),
+            event(3, "task-lt-test", "claimed", Some("task-lt-test")),
+        ];
+        let comments = ["Rejected: the parser has no tests"];
+
+        let section = history_section(&events, comments).expect("history");
+        assert!(section.starts_with("## Previous attempts and feedback"));
+        assert!(section.contains("- Attempt 1: rejected in review"));
+        assert!(
+            !section.contains("Attempt 2"),
+            "current attempt is not history"
+        );
+        assert!(section.contains("- Rejected: the parser has no tests"));
+    }
+
+    #[test]
+    fn oldest_comments_are_dropped_over_budget() {
+        let long = "x".repeat(COMMENT_MAX_CHARS);
+        let mut comments: Vec<&str> = vec!["first comment"];
+        comments.extend(std::iter::repeat_n(long.as_str(), 6));
+        comments.push("newest comment");
+
+        let section = history_section(&[], comments).expect("history");
+        assert!(section.len() <= HISTORY_TOKEN_BUDGET * CHARS_PER_TOKEN);
+        assert!(!section.contains("first comment"));
+        assert!(section.contains("earlier comments omitted"));
+        assert!(section.contains("newest comment"));
+    }
+}
@@ -0,0 +1,178 @@
+//! Pre-review verification of completed tasks.
+//!
+//! When a project sets `verify_command`, an agent's completion does not go
+//! straight to review: the command runs in the task worktree, inside the
+//! agent's sandbox, while the agent stays alive. A failure is sent back to
+//! the agent as `check_failed` up to `MAX_CHECK_FOLLOWUPS` times; after that,
+//! or on success, the task goes to review with the result attached.
+
+use std::path::PathBuf;
+
+use super::OrchestratorRuntime;
+use crate::events::EventKind;
+use crate::runtime_support as support;
+use crate::types::AgentRole;
+use crate::worktree::WorktreeConfig;
+
+/// Failed checks sent back to the agent before the task goes to review anyway.
+pub const MAX_CHECK_FOLLOWUPS: u32 = 2;
+const CHECK_COMMENT_MAX_CHARS: usize = 3000;
+
+impl OrchestratorRuntime {
+    /// Run the project's check for a completed task in the background; the
+    /// result comes back as `check_result`. Returns false if there is no check
+    /// or no task worktree to run it in.
+    pub(super) fn start_verification(
+        &mut self,
+        task_id: &str,
+        agent_name: &str,
+        content: &str,
+    ) -> bool {
+        let Some(command) = self.settings.verify_command.clone() else {
+            return false;
+        };
+        let project_path = PathBuf::from(&self.working_dir);
+        let worktree = WorktreeConfig {
+            project_dir: project_path.clone(),
+            agent_name: agent_name.to_string(),
+            target_branch: self.settings.default_branch.clone(),
+        }
+        .path();
+        // The project checkout holds the target branch, not the agent's work.
+        if !worktree.exists() {
+            tracing::warn!(
+                "Skipping verification of task {}: worktree {} is missing",
+                task_id,
+                worktree.display()
+            );
+            return false;
+        }
+        let cwd = worktree;
+        let use_sandbox = self.settings.sandbox && llm_sdk::sandbox::is_available();
+        let (_, prefix) = support::resolve_sandbox_with(
+            AgentRole::TaskAgent,
+            &project_path,
+            Ok(cwd.clone()),
+            use_sandbox,
+            &self.sandbox_extras(agent_name),
+        );
+        // The agent is idle while the check runs; don't let the watchdog reclaim it.
+        self.dispatcher.record_activity(agent_name);
+        tracing::info!("Verifying task {} with `{}`", task_id, command);
+
+        let bus = self.bus.clone();
+        let timeout = self.settings.verify_timeout;
+        let mut payload = serde_json::json!({
+            "task_id": task_id,
+            "agent": agent_name,
+            "content": content,
+        });
+        tokio::spawn(async move {
+            let outcome = support::run_check_command(&command, &cwd, &prefix, timeout).await;
+            payload["passed"] = outcome.passed.into();
+            payload["log"] = outcome.log.into();
+            let name = format!("check-{}", support::payload_str(&payload, "agent"));
+            match bus.register(&name) {
+                Ok(mailbox) => {
+                    let _ = mailbox.send("runtime", "check_result", payload);
+                }
+                Err(e) => tracing::warn!("Failed to report check result: {}", e),
+            }
+            bus.deregister(&name);
+        });
+        true
+    }
+
+    /// Record a check result, then send the failure back to the agent or
+    /// hand the task to review.
+    pub(super) async fn handle_check_result(&mut self, payload: &serde_json::Value) {
+        let task_id = support::payload_str(payload, "task_id");
+        let agent = support::payload_str(payload, "agent");
+        let content = support::payload_str(payload, "content");
+        let log = support::payload_str(payload, "log");
+        let passed = payload
+            .get("passed")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if self.dispatcher.task_id_for_agent_name(&agent).as_deref() != Some(task_id.as_str()) {
+            tracing::info!("Dropping check result for {task_id}: {agent} no longer holds it");
+            self.check_followups.remove(&task_id);
+            return;
+        }
+
+        let command = self.settings.verify_command.clone().unwrap_or_default();
+        let report = check_report(&command, passed, &log);
+        let _ = self
+            .db
+            .add_comment(
+                &task_id,
+                "runtime",
+                &claude_architect::truncate(&report, CHECK_COMMENT_MAX_CHARS),
+            )
+            .await;
+        let verdict = if passed { "passed" } else { "failed" };
+        self.emit(
+            EventKind::CheckResult,
+            Some(&task_id),
+            Some(&agent),
+            Some(verdict.to_string()),
+        );
+
+        let followups = self.check_followups.get(&task_id).copied().unwrap_or(0);
+        if !passed && followups < MAX_CHECK_FOLLOWUPS {
+            let attempt = followups + 1;
+            let message = check_failed_prompt(&command, &log, attempt);
+            let payload = serde_json::json!({"content": message, "task_id": task_id});
+            match self.dispatcher.notify(&agent, "check_failed", payload) {
+                Ok(()) => {
+                    self.check_followups.insert(task_id, attempt);
+                    self.dispatcher.record_activity(&agent);
+                    return;
+                }
+                Err(e) => tracing::warn!("Failed to send check failure to {}: {}", agent, e),
+            }
+        }
+        self.check_followups.remove(&task_id);
+        self.finish_agent_task(&task_id, &agent, &content, Some(report))
+            .await;
+    }
+}
+
+fn check_report(command: &str, passed: bool, log: &str) -> String {
+    if passed {
+        format!("Check `{command}` passed.")
+    } else {
+        format!("Check `{command}` failed:\n\n```\n{log}\n```")
+    }
+}
+
+fn check_failed_prompt(command: &str, log: &str, attempt: u32) -> String {
+    format!(
+        "The project check `{command}` failed on your branch \
+         (round {attempt} of {MAX_CHECK_FOLLOWUPS}). Fix the problems, commit, \
+         and report completion again.\n\n```\n{log}\n```"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_includes_log_only_on_failure() {
+        assert_eq!(
+            check_report("cargo test", true, "ok"),
+            "Check `cargo test` passed."
+        );
+        let failed = check_report("cargo test", false, "error[E0308]");
+        assert!(failed.starts_with("Check `cargo test` failed"));
+        assert!(failed.contains("error[E0308]"));
+    }
+
+    #[test]
+    fn failure_prompt_counts_rounds() {
+        let prompt = check_failed_prompt("npm test", "1 failing", 1);
+        assert!(prompt.contains("round 1 of 2"));
+        assert!(prompt.contains("1 failing"));
+    }
+}
@@ -35,6 +35,68 @@ impl CommandTimers {
     }
 }
 
+/// Maximum characters of command output kept from a check run.
+const CHECK_LOG_MAX_CHARS: usize = 4000;
+
+/// Result of running a project build/test command.
+pub struct CheckOutcome {
+    pub passed: bool,
+    /// Tail of combined stdout/stderr.
+    pub log: String,
+}
+
+/// Run `command` through `sh -c` in `cwd` (or inside `prefix` when sandboxed),
+/// failing it if it exceeds `timeout`.
+pub async fn run_check_command(
+    command: &str,
+    cwd: &Path,
+    prefix: &[String],
+    timeout: Duration,
+) -> CheckOutcome {
+    let mut cmd = match prefix.split_first() {
+        Some((program, args)) => {
+            let mut cmd = tokio::process::Command::new(program);
+            cmd.args(args);
+            cmd
+        }
+        None => tokio::process::Command::new("sh"),
+    };
+    if !prefix.is_empty() {
+        cmd.arg("sh");
+    }
+    cmd.args(["-c", command])
+        .current_dir(cwd)
+        .kill_on_drop(true);
+
+    match tokio::time::timeout(timeout, cmd.output()).await {
+        Ok(Ok(output)) => {
+            let mut log = String::from_utf8_lossy(&output.stdout).into_owned();
+            log.push_str(&String::from_utf8_lossy(&output.stderr));
+            CheckOutcome {
+                passed: output.status.success(),
+                log: tail_chars(&log, CHECK_LOG_MAX_CHARS),
+            }
+        }
+        Ok(Err(e)) => CheckOutcome {
+            passed: false,
+            log: format!("failed to run `{command}`: {e}"),
+        },
+        Err(_) => CheckOutcome {
+            passed: false,
+            log: format!("`{command}` timed out after {}s", timeout.as_secs()),
+        },
+    }
+}
+
+/// Last `max_chars` characters of `text` (char-boundary safe).
+pub fn tail_chars(text: &str, max_chars: usize) -> String {
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text.to_string();
+    }
+    text.chars().skip(count - max_chars).collect()
+}
+
 pub async fn open_test_stores() -> Result<(Database, SessionStore)> {
     let tmp = std::env::temp_dir().join(format!(
         "orch-test-{}-{}",
@@ -56,16 +118,112 @@ pub fn payload_str(payload: &serde_json::Value, key: &str) -> String {
         .to_string()
 }
 
+/// Task IDs of every `agent/task-<id>` branch mentioned in free text, in order.
+pub fn mentioned_task_ids(text: &str) -> Vec<String> {
+    const MARKER: &str = "agent/task-";
+    let mut ids = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(MARKER) {
+        rest = &rest[start + MARKER.len()..];
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(rest.len());
+        let id = rest[..end].trim_end_matches('-');
+        if !id.is_empty() && !ids.iter().any(|known| known == id) {
+            ids.push(id.to_string());
+        }
+        rest = &rest[end..];
+    }
+    ids
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Parse a task DB timestamp (`2026-03-12T10:00:00Z` or `2026-03-12 10:00:00`,
+/// always UTC) into Unix seconds. Fractional seconds and offsets are ignored.
+pub fn parse_db_timestamp(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let date = text.get(..10)?;
+    let time = text.get(11..19)?;
+    if !matches!(text.as_bytes().get(10), Some(b'T' | b' ')) {
+        return None;
+    }
+    let mut date_parts = date.split('-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (
+        date_parts.next()??,
+        date_parts.next()??,
+        date_parts.next()??,
+    );
+    let mut time_parts = time.split(':').map(|p| p.parse::<i64>().ok());
+    let (hour, minute, second) = (
+        time_parts.next()??,
+        time_parts.next()??,
+        time_parts.next()??,
+    );
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+    u64::try_from(secs).ok()
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
 pub fn is_worktree_role(bus_name: &str) -> bool {
     bus_name.starts_with("task-")
 }
 
+/// Placeholder in sandbox mounts for the agent's worktree path.
+pub const WORKTREE_PLACEHOLDER: &str = "{worktree}";
+
+/// Per-project additions to the developer sandbox.
+#[derive(Clone, Debug, Default)]
+pub struct SandboxExtras {
+    /// Writable binds as (host path, sandbox path); either may contain
+    /// [`WORKTREE_PLACEHOLDER`].
+    pub mounts: Vec<(String, String)>,
+    /// Environment variables set inside the sandbox.
+    pub env: Vec<(String, String)>,
+}
+
 /// Determine working directory and sandbox prefix for an agent.
 pub fn resolve_sandbox(
     role: AgentRole,
     project_path: &Path,
     worktree_result: Result<PathBuf>,
     use_sandbox: bool,
+) -> (String, Vec<String>) {
+    resolve_sandbox_with(
+        role,
+        project_path,
+        worktree_result,
+        use_sandbox,
+        &SandboxExtras::default(),
+    )
+}
+
+/// Like [`resolve_sandbox`], adding the project's extra mounts to developer sandboxes.
+pub fn resolve_sandbox_with(
+    role: AgentRole,
+    project_path: &Path,
+    worktree_result: Result<PathBuf>,
+    use_sandbox: bool,
+    extras: &SandboxExtras,
 ) -> (String, Vec<String>) {
     let is_dev = matches!(role, AgentRole::TaskAgent | AgentRole::Merger);
 
@@ -74,10 +232,14 @@ pub fn resolve_sandbox(
         if use_sandbox {
             let git_dir = find_git_dir(project_path);
             let mut prefix = llm_sdk::sandbox::developer_prefix(&dev_path, git_dir.as_deref());
-            add_support_mounts(&mut prefix, project_path, &dev_path);
+            add_extra_mounts(&mut prefix, &extras.mounts, &dev_path);
+            add_sandbox_env(&mut prefix, &extras.env);
             return (llm_sdk::sandbox::REPO_MOUNT.to_string(), prefix);
         }
-        return (dev_path.to_string_lossy().into_owned(), Vec::new());
+        return (
+            dev_path.to_string_lossy().into_owned(),
+            env_prefix(&extras.env),
+        );
     }
 
     if use_sandbox {
@@ -88,6 +250,16 @@ pub fn resolve_sandbox(
     }
 }
 
+/// `env K=V ...`, so unsandboxed commands still get the sandbox environment.
+fn env_prefix(env: &[(String, String)]) -> Vec<String> {
+    if env.is_empty() {
+        return Vec::new();
+    }
+    std::iter::once("env".to_string())
+        .chain(env.iter().map(|(key, value)| format!("{key}={value}")))
+        .collect()
+}
+
 /// Find the `.git` directory for a project (resolves worktree indirection).
 fn find_git_dir(project_path: &Path) -> Option<PathBuf> {
     let git_path = project_path.join(".git");
@@ -131,39 +303,36 @@ pub fn build_mcp_config(agent_name: &str, project: &str) -> String {
     .to_string()
 }
 
-fn add_support_mounts(prefix: &mut Vec<String>, project_path: &Path, dev_path: &Path) {
-    for (host_path, mount_path) in support_mounts(project_path, dev_path) {
+/// Bind each mount, expanding `{worktree}` to the agent's working tree.
+fn add_extra_mounts(prefix: &mut Vec<String>, mounts: &[(String, String)], dev_path: &Path) {
+    let worktree = dev_path
+        .canonicalize()
+        .unwrap_or_else(|_| dev_path.to_path_buf())
+        .to_string_lossy()
+        .into_owned();
+    for (host_path, mount_path) in mounts {
+        let host_path = host_path.replace(WORKTREE_PLACEHOLDER, &worktree);
+        let mount_path = mount_path.replace(WORKTREE_PLACEHOLDER, &worktree);
+        if !Path::new(&host_path).exists() {
+            tracing::warn!("Skipping extra sandbox mount {host_path}: path does not exist");
+            continue;
+        }
         insert_rw_bind(prefix, &host_path, &mount_path);
     }
 }
 
-fn support_mounts(project_path: &Path, dev_path: &Path) -> Vec<(String, String)> {
-    let mut mounts = Vec::new();
-    let Some(project_name) = project_path.file_name().and_then(|n| n.to_str()) else {
-        return mounts;
-    };
-    let Some(alias_name) = project_name.strip_suffix("-phpstan-fixes") else {
-        return mounts;
-    };
-    if !project_path.join(".worktrees").join(alias_name).exists() {
-        return mounts;
+fn add_sandbox_env(prefix: &mut Vec<String>, env: &[(String, String)]) {
+    for (name, value) in env {
+        insert_before_chdir(
+            prefix,
+            ["--setenv".to_string(), name.clone(), value.clone()],
+        );
     }
-    let host = dev_path
-        .canonicalize()
-        .unwrap_or_else(|_| dev_path.to_path_buf())
-        .to_string_lossy()
-        .into_owned();
-    mounts.push((host, format!("/tmp/{alias_name}")));
-    mounts
 }
 
 fn insert_rw_bind(prefix: &mut Vec<String>, host_path: &str, mount_path: &str) {
-    let insert_at = prefix
-        .iter()
-        .position(|arg| arg == "--chdir")
-        .unwrap_or(prefix.len().saturating_sub(1));
-    prefix.splice(
-        insert_at..insert_at,
+    insert_before_chdir(
+        prefix,
         [
             "--bind".to_string(),
             host_path.to_string(),
@@ -172,11 +341,44 @@ fn insert_rw_bind(prefix: &mut Vec<String>, host_path: &str, mount_path: &str) {
     );
 }
 
+fn insert_before_chdir(prefix: &mut Vec<String>, args: [String; 3]) {
+    let insert_at = prefix
+        .iter()
+        .position(|arg| arg == "--chdir")
+        .unwrap_or(prefix.len().saturating_sub(1));
+    prefix.splice(insert_at..insert_at, args);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use anyhow::anyhow;
 
+    #[test]
+    fn parse_db_timestamp_accepts_rfc3339_and_sqlite_formats() {
+        assert_eq!(parse_db_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_db_timestamp("2026-03-12T00:00:00Z"),
+            Some(1_773_273_600)
+        );
+        assert_eq!(
+            parse_db_timestamp("2026-03-12 00:00:00"),
+            Some(1_773_273_600)
+        );
+        assert_eq!(
+            parse_db_timestamp("2024-02-29T12:34:56.789+00:00"),
+            Some(1_709_210_096)
+        );
+    }
+
+    #[test]
+    fn parse_db_timestamp_rejects_garbage() {
+        assert_eq!(parse_db_timestamp(""), None);
+        assert_eq!(parse_db_timestamp("yesterday"), None);
+        assert_eq!(parse_db_timestamp("2026-13-01T00:00:00Z"), None);
+        assert_eq!(parse_db_timestamp("2026-03-12X00:00:00"), None);
+    }
+
     fn temp_dir(name: &str) -> PathBuf {
         let path = std::env::temp_dir().join(format!(
             "agent_orchestrator_runtime_{name}_{}",
@@ -198,6 +400,51 @@ mod tests {
         assert_eq!(payload_str(&payload, "missing"), "");
     }
 
+    #[test]
+    fn mentioned_task_ids_extracts_branch_suffixes() {
+        let text = "Merged `agent/task-lt-abc1` into master. Conflict in agent/task-lt-9f2e-.";
+
+        assert_eq!(mentioned_task_ids(text), vec!["lt-abc1", "lt-9f2e"]);
+        assert!(mentioned_task_ids("merged everything").is_empty());
+        assert_eq!(
+            mentioned_task_ids("agent/task-x then agent/task-x"),
+            vec!["x"]
+        );
+    }
+
+    #[test]
+    fn tail_chars_keeps_end_on_char_boundaries() {
+        assert_eq!(tail_chars("short", 10), "short");
+        assert_eq!(tail_chars("abcdef", 3), "def");
+        assert_eq!(tail_chars("ééé✓", 2), "é✓");
+    }
+
+    #[tokio::test]
+    async fn run_check_command_reports_exit_status_and_output() {
+        let dir = temp_dir("check_command");
+
+        let passed = run_check_command("echo ok", &dir, &[], Duration::from_secs(5)).await;
+        let failed =
+            run_check_command("echo broken >&2; exit 3", &dir, &[], Duration::from_secs(5)).await;
+
+        assert!(passed.passed);
+        assert_eq!(passed.log.trim(), "ok");
+        assert!(!failed.passed);
+        assert!(failed.log.contains("broken"));
+
+        let env = env_prefix(&[("CHECK_VAR".to_string(), "set".to_string())]);
+        let with_env = run_check_command(
+            "test \"$CHECK_VAR\" = set",
+            &dir,
+            &env,
+            Duration::from_secs(5),
+        )
+        .await;
+        assert!(with_env.passed, "{}", with_env.log);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
     #[test]
     fn worktree_role_detection_uses_task_prefix() {
         assert!(is_worktree_role("task-123"));
@@ -220,6 +467,31 @@ mod tests {
         std::fs::remove_dir_all(worktree).ok();
     }
 
+    #[test]
+    fn unsandboxed_developer_gets_sandbox_env_through_env_prefix() {
+        let project = temp_dir("env_project");
+        let extras = SandboxExtras {
+            mounts: Vec::new(),
+            env: vec![(
+                "CARGO_TARGET_DIR".to_string(),
+                "/cache/target-0".to_string(),
+            )],
+        };
+
+        let (cwd, prefix) = resolve_sandbox_with(
+            AgentRole::TaskAgent,
+            &project,
+            Ok(project.clone()),
+            false,
+            &extras,
+        );
+
+        assert_eq!(cwd, project.to_string_lossy());
+        assert_eq!(prefix, vec!["env", "CARGO_TARGET_DIR=/cache/target-0"]);
+
+        std::fs::remove_dir_all(project).ok();
+    }
+
     #[test]
     fn resolve_sandbox_falls_back_to_project_when_worktree_fails() {
         let project = temp_dir("fallback");
@@ -258,6 +530,33 @@ mod tests {
         std::fs::remove_dir_all(worktree).ok();
     }
 
+    #[test]
+    fn sandboxed_developer_gets_extra_env() {
+        let project = temp_dir("sandbox_env_project");
+        let worktree = temp_dir("sandbox_env_worktree");
+        let extras = SandboxExtras {
+            env: vec![("CARGO_TARGET_DIR".to_string(), "/cache/0".to_string())],
+            ..Default::default()
+        };
+
+        let (_, prefix) = resolve_sandbox_with(
+            AgentRole::TaskAgent,
+            &project,
+            Ok(worktree.clone()),
+            true,
+            &extras,
+        );
+
+        assert!(
+            prefix
+                .windows(3)
+                .any(|w| w[0] == "--setenv" && w[1] == "CARGO_TARGET_DIR" && w[2] == "/cache/0")
+        );
+
+        std::fs::remove_dir_all(project).ok();
+        std::fs::remove_dir_all(worktree).ok();
+    }
+
     #[test]
     fn build_mcp_config_contains_socket_and_agent() {
         let config: serde_json::Value =
@@ -0,0 +1,95 @@
+//! Daemon state that survives restarts but is not user configuration.
+//!
+//! Kept out of projects.toml: the supervisor restarts a project whenever its
+//! config entry changes, and pausing must not touch running agents.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DaemonState {
+    /// Projects whose dispatching is paused.
+    #[serde(default)]
+    pub paused: BTreeSet<String>,
+}
+
+pub fn state_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("agent-orchestrator/state.json")
+}
+
+impl DaemonState {
+    /// Load the saved state, starting empty if it is missing or unreadable.
+    pub fn load() -> Self {
+        let path = state_path();
+        Self::load_from(&path).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring daemon state {}: {e:#}", path.display());
+            Self::default()
+        })
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let contents = serde_json::to_string_pretty(self)?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, contents)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Record whether `project` is paused in the saved state.
+pub fn set_paused(project: &str, paused: bool) -> Result<()> {
+    let path = state_path();
+    let mut state = DaemonState::load_from(&path)?;
+    let changed = if paused {
+        state.paused.insert(project.to_string())
+    } else {
+        state.paused.remove(project)
+    };
+    if changed {
+        state.save_to(&path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trips_and_missing_file_is_empty() {
+        let dir = std::env::temp_dir().join(format!("orchestrator-state-{}", std::process::id()));
+        let path = dir.join("state.json");
+        assert_eq!(
+            DaemonState::load_from(&path).unwrap(),
+            DaemonState::default()
+        );
+
+        let mut state = DaemonState::default();
+        state.paused.insert("my-project".to_string());
+        state.save_to(&path).unwrap();
+
+        assert_eq!(DaemonState::load_from(&path).unwrap(), state);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -8,7 +8,10 @@ use std::process::Command;
 
 use anyhow::{Context, Result};
 
-const SHARED_DEPENDENCY_DIRS: &[&str] = &["vendor", "node_modules"];
+use crate::config::{BuildCache, WorktreeSetup};
+
+/// Directories linked into worktrees when a project doesn't configure `link`.
+pub const SHARED_DEPENDENCY_DIRS: &[&str] = &["vendor", "node_modules"];
 
 pub struct WorktreeConfig {
     pub project_dir: PathBuf,
@@ -26,38 +29,80 @@ impl WorktreeConfig {
     }
 }
 
-pub fn create_worktree(cfg: &WorktreeConfig) -> Result<PathBuf> {
-    create_worktree_inner(cfg, false)
+/// A worktree ready for an agent.
+#[derive(Clone, Debug)]
+pub struct PreparedWorktree {
+    pub path: PathBuf,
+    /// Newly added rather than reused; the project's `post_create` command
+    /// has not run in it yet.
+    pub fresh: bool,
+    /// Reused as-is because its uncommitted work could not be saved: it is
+    /// still on the previous attempt's branch state, not on the target branch.
+    pub kept: bool,
+}
+
+pub fn create_worktree(cfg: &WorktreeConfig, setup: &WorktreeSetup) -> Result<PreparedWorktree> {
+    create_worktree_inner(cfg, setup, false)
 }
 
 /// Create or reuse a worktree, preserving the branch state when `resume` is true.
 /// Used on restart to keep partial work from a previous session.
-pub fn create_or_resume_worktree(cfg: &WorktreeConfig) -> Result<PathBuf> {
-    create_worktree_inner(cfg, true)
+pub fn create_or_resume_worktree(
+    cfg: &WorktreeConfig,
+    setup: &WorktreeSetup,
+) -> Result<PreparedWorktree> {
+    create_worktree_inner(cfg, setup, true)
 }
 
-fn create_worktree_inner(cfg: &WorktreeConfig, resume: bool) -> Result<PathBuf> {
+fn create_worktree_inner(
+    cfg: &WorktreeConfig,
+    setup: &WorktreeSetup,
+    resume: bool,
+) -> Result<PreparedWorktree> {
     ensure_git_repo(&cfg.project_dir)?;
     ensure_head_exists(&cfg.project_dir)?;
     prune_stale_worktrees(&cfg.project_dir);
     let path = cfg.path();
 
-    if let Some(reused) = try_reuse_worktree(cfg, &path, resume) {
-        return Ok(reused);
+    if let Some(prepared) = try_reuse_worktree(cfg, setup, &path, resume) {
+        return Ok(prepared);
     }
-    add_fresh_worktree(cfg, &path)
+    let path = add_fresh_worktree(cfg, setup, &path)?;
+    Ok(PreparedWorktree {
+        path,
+        fresh: true,
+        kept: false,
+    })
 }
 
-fn try_reuse_worktree(cfg: &WorktreeConfig, path: &PathBuf, resume: bool) -> Option<PathBuf> {
+fn try_reuse_worktree(
+    cfg: &WorktreeConfig,
+    setup: &WorktreeSetup,
+    path: &PathBuf,
+    resume: bool,
+) -> Option<PreparedWorktree> {
     if !path.join(".git").exists() {
         return None;
     }
+    let reused = |kept| PreparedWorktree {
+        path: path.clone(),
+        fresh: false,
+        kept,
+    };
     if resume {
         tracing::info!(
             "Resuming worktree at {} (preserving branch state)",
             path.display()
         );
-        return Some(path.clone());
+        return Some(reused(false));
+    }
+    if let Err(e) = save_wip(cfg, setup) {
+        tracing::warn!(
+            "Keeping worktree at {} as-is, could not save uncommitted work: {:#}",
+            path.display(),
+            e
+        );
+        return Some(reused(true));
     }
     tracing::info!("Reusing existing worktree at {}", path.display());
     let branch = cfg.branch();
@@ -68,18 +113,23 @@ fn try_reuse_worktree(cfg: &WorktreeConfig, path: &PathBuf, resume: bool) -> Opt
         .is_ok_and(|s| s.success());
     if !reset_ok {
         tracing::warn!("Branch switch failed, removing and recreating worktree");
-        let _ = remove_worktree(cfg);
+        let _ = remove_worktree(cfg, setup);
         return None;
     }
     let _ = Command::new("git")
         .args(["reset", "--hard", "HEAD"])
         .current_dir(path)
         .status();
-    prepare_worktree_support_links(&cfg.project_dir, path);
-    Some(path.clone())
+    prepare_worktree_support_links(&cfg.project_dir, path, setup);
+    copy_setup_files(&cfg.project_dir, path, &setup.copy);
+    Some(reused(false))
 }
 
-fn add_fresh_worktree(cfg: &WorktreeConfig, path: &std::path::Path) -> Result<PathBuf> {
+fn add_fresh_worktree(
+    cfg: &WorktreeConfig,
+    setup: &WorktreeSetup,
+    path: &std::path::Path,
+) -> Result<PathBuf> {
     let branch = cfg.branch();
     let path_str = path.to_str().context("worktree path is not valid UTF-8")?;
     let status = Command::new("git")
@@ -98,18 +148,30 @@ fn add_fresh_worktree(cfg: &WorktreeConfig, path: &std::path::Path) -> Result<Pa
     if !status.success() {
         anyhow::bail!("git worktree add failed with status {}", status);
     }
-    prepare_worktree_support_links(&cfg.project_dir, path);
+    prepare_worktree_support_links(&cfg.project_dir, path, setup);
+    copy_setup_files(&cfg.project_dir, path, &setup.copy);
+    if setup.build_cache == BuildCache::Reflink {
+        reflink_target_dir(&cfg.project_dir, path);
+    }
     Ok(path.to_path_buf())
 }
 
-fn prepare_worktree_support_links(project_dir: &std::path::Path, worktree_path: &std::path::Path) {
-    link_shared_dependency_dirs(project_dir, worktree_path);
+fn prepare_worktree_support_links(
+    project_dir: &std::path::Path,
+    worktree_path: &std::path::Path,
+    setup: &WorktreeSetup,
+) {
+    link_shared_dependency_dirs(project_dir, worktree_path, &setup.link);
     link_project_root_alias(project_dir);
-    link_worktree_aliases(project_dir, worktree_path);
+    link_worktree_aliases(project_dir, worktree_path, &setup.aliases);
 }
 
-pub fn link_shared_dependency_dirs(project_dir: &std::path::Path, worktree_path: &std::path::Path) {
-    for name in SHARED_DEPENDENCY_DIRS {
+pub fn link_shared_dependency_dirs(
+    project_dir: &std::path::Path,
+    worktree_path: &std::path::Path,
+    dirs: &[String],
+) {
+    for name in dirs {
         let source = project_dir.join(name);
         if !source.exists() {
             continue;
@@ -148,19 +210,88 @@ pub fn link_project_root_alias(project_dir: &std::path::Path) {
     ensure_symlink(&alias, project_dir);
 }
 
-pub fn link_worktree_aliases(project_dir: &std::path::Path, worktree_path: &std::path::Path) {
-    let Some(project_name) = project_dir.file_name().and_then(|n| n.to_str()) else {
+/// Point each `.worktrees/<alias>` symlink at `worktree_path`, so tools
+/// configured with a fixed path follow the most recently prepared worktree.
+pub fn link_worktree_aliases(
+    project_dir: &std::path::Path,
+    worktree_path: &std::path::Path,
+    aliases: &[String],
+) {
+    if aliases.is_empty() {
         return;
-    };
-    let Some(alias_name) = project_name.strip_suffix("-phpstan-fixes") else {
-        return;
-    };
+    }
     let worktrees_dir = project_dir.join(".worktrees");
     if std::fs::create_dir_all(&worktrees_dir).is_err() {
         return;
     }
-    let alias = worktrees_dir.join(alias_name);
-    ensure_symlink(&alias, worktree_path);
+    for alias in aliases {
+        ensure_symlink(&worktrees_dir.join(alias), worktree_path);
+    }
+}
+
+/// Copy project-root files or directories into a new worktree, skipping
+/// any that are missing in the project or already present in the worktree.
+pub fn copy_setup_files(
+    project_dir: &std::path::Path,
+    worktree_path: &std::path::Path,
+    paths: &[String],
+) {
+    for name in paths {
+        let source = project_dir.join(name);
+        let dest = worktree_path.join(name);
+        if !source.exists() || std::fs::symlink_metadata(&dest).is_ok() {
+            continue;
+        }
+        if let Err(e) = copy_recursive(&source, &dest) {
+            tracing::warn!(
+                "Failed to copy {} into {}: {}",
+                source.display(),
+                worktree_path.display(),
+                e
+            );
+        }
+    }
+}
+
+fn copy_recursive(source: &std::path::Path, dest: &std::path::Path) -> std::io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if !source.is_dir() {
+        std::fs::copy(source, dest)?;
+        return Ok(());
+    }
+    std::fs::create_dir_all(dest)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        copy_recursive(&entry.path(), &dest.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// Clone the project's `target/` into a new worktree with copy-on-write
+/// extents. Skipped, never copied in full, where the filesystem can't reflink.
+fn reflink_target_dir(project_dir: &std::path::Path, worktree_path: &std::path::Path) {
+    let source = project_dir.join("target");
+    let dest = worktree_path.join("target");
+    if !source.is_dir() || dest.exists() {
+        return;
+    }
+    let cloned = Command::new("cp")
+        .arg("-a")
+        .arg("--reflink=always")
+        .arg(&source)
+        .arg(&dest)
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+    if !cloned {
+        tracing::warn!(
+            "Could not reflink {} (filesystem without copy-on-write?), building from scratch",
+            source.display()
+        );
+        let _ = std::fs::remove_dir_all(&dest);
+    }
 }
 
 fn ensure_symlink(alias: &std::path::Path, target: &std::path::Path) {
@@ -244,7 +375,10 @@ fn ensure_head_exists(project_dir: &std::path::Path) -> Result<()> {
     Ok(())
 }
 
-pub fn remove_worktree(cfg: &WorktreeConfig) -> Result<()> {
+/// Remove the worktree, first saving any uncommitted work under a WIP ref.
+/// Fails without removing anything if that snapshot can't be taken.
+pub fn remove_worktree(cfg: &WorktreeConfig, setup: &WorktreeSetup) -> Result<()> {
+    save_wip(cfg, setup).context("not removing worktree with unsaved changes")?;
     let path = cfg.path();
     let remove_status = Command::new("git")
         .args([
@@ -273,3 +407,345 @@ pub fn remove_worktree(cfg: &WorktreeConfig) -> Result<()> {
 
     Ok(())
 }
+
+/// Ref namespace for snapshots of uncommitted worktree changes, one ref per
+/// snapshot at `{WIP_REF_PREFIX}/{agent_name}/{unix_secs}`.
+pub const WIP_REF_PREFIX: &str = "refs/orchestrator/wip";
+
+/// A saved snapshot of uncommitted work.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WipRef {
+    pub name: String,
+    pub agent_name: String,
+    pub saved_at: u64,
+    pub commit: String,
+    pub subject: String,
+}
+
+/// Where `restore_wip` put the snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WipRestore {
+    /// Applied as uncommitted changes in the agent's existing worktree.
+    Applied(PathBuf),
+    /// No worktree to apply to; a branch now points at the snapshot commit.
+    Branch(String),
+}
+
+/// Snapshot uncommitted changes in the worktree, untracked files included,
+/// as a commit on top of HEAD stored under a WIP ref. The worktree and its
+/// index are left untouched. Returns the ref, or None when the tree is clean.
+/// What `setup` linked or copied into the worktree is not work and is left out.
+pub fn save_wip(cfg: &WorktreeConfig, setup: &WorktreeSetup) -> Result<Option<String>> {
+    let path = cfg.path();
+    if !path.join(".git").exists() {
+        return Ok(None);
+    }
+    let pathspec = wip_pathspec(setup);
+    let mut status_args = vec!["status", "--porcelain", "--untracked-files=all"];
+    status_args.extend(pathspec.iter().map(String::as_str));
+    let changes = git_stdout(&path, &status_args)?;
+    if changes.is_empty() {
+        return Ok(None);
+    }
+    let head = git_stdout(&path, &["rev-parse", "HEAD"])?;
+    let tree = write_worktree_tree(&path, &cfg.agent_name, &pathspec)?;
+    let message = format!(
+        "WIP from {} ({} changed paths)",
+        cfg.agent_name,
+        changes.lines().count()
+    );
+    let commit = git_stdout(&path, &["commit-tree", &tree, "-p", &head, "-m", &message])?;
+    let name = format!(
+        "{}/{}/{}",
+        WIP_REF_PREFIX,
+        cfg.agent_name,
+        crate::runtime_support::unix_now()
+    );
+    git_stdout(&path, &["update-ref", &name, &commit])?;
+    tracing::info!("Saved uncommitted work in {} to {}", path.display(), name);
+    Ok(Some(name))
+}
+
+/// Everything except the setup's linked dirs and copied files, which every
+/// worktree gets and would otherwise make it look dirty.
+fn wip_pathspec(setup: &WorktreeSetup) -> Vec<String> {
+    let mut spec = vec!["--".to_string(), ".".to_string()];
+    spec.extend(
+        setup
+            .link
+            .iter()
+            .chain(&setup.copy)
+            .map(|path| format!(":(exclude){path}")),
+    );
+    spec
+}
+
+/// Tree object for the worktree's files, built in a throwaway index.
+fn write_worktree_tree(
+    path: &std::path::Path,
+    agent_name: &str,
+    pathspec: &[String],
+) -> Result<String> {
+    let index = std::env::temp_dir().join(format!(
+        "orchestrator-wip-{}-{}.index",
+        agent_name,
+        std::process::id()
+    ));
+    let mut add_args = vec!["add", "-A"];
+    add_args.extend(pathspec.iter().map(String::as_str));
+    let result = git_stdout_with_index(path, &index, &["read-tree", "HEAD"])
+        .and_then(|_| git_stdout_with_index(path, &index, &add_args))
+        .and_then(|_| git_stdout_with_index(path, &index, &["write-tree"]));
+    let _ = std::fs::remove_file(&index);
+    result
+}
+
+/// WIP snapshots in the project, newest first.
+pub fn list_wip_refs(project_dir: &std::path::Path) -> Result<Vec<WipRef>> {
+    let output = git_stdout(
+        project_dir,
+        &[
+            "for-each-ref",
+            "--format=%(refname)%09%(objectname)%09%(subject)",
+            WIP_REF_PREFIX,
+        ],
+    )?;
+    let mut refs: Vec<WipRef> = output.lines().filter_map(parse_wip_line).collect();
+    refs.sort_by(|a, b| b.saved_at.cmp(&a.saved_at).then(a.name.cmp(&b.name)));
+    Ok(refs)
+}
+
+fn parse_wip_line(line: &str) -> Option<WipRef> {
+    let mut fields = line.splitn(3, '\t');
+    let name = fields.next()?;
+    let commit = fields.next()?;
+    let subject = fields.next().unwrap_or_default();
+    let rest = name.strip_prefix(WIP_REF_PREFIX)?.strip_prefix('/')?;
+    let (agent_name, saved_at) = rest.rsplit_once('/')?;
+    Some(WipRef {
+        name: name.to_string(),
+        agent_name: agent_name.to_string(),
+        saved_at: saved_at.parse().ok()?,
+        commit: commit.to_string(),
+        subject: subject.to_string(),
+    })
+}
+
+/// Bring a WIP snapshot back. `name` is the full ref or the part after
+/// `WIP_REF_PREFIX` (`task-lt-abc/1700000000`). If the agent's worktree
+/// exists the changes are re-applied there, uncommitted; otherwise a
+/// `wip/<agent>-<secs>` branch is created at the snapshot. The ref is kept.
+pub fn restore_wip(project_dir: &std::path::Path, name: &str) -> Result<WipRestore> {
+    let full = if name.starts_with("refs/") {
+        name.to_string()
+    } else {
+        format!("{}/{}", WIP_REF_PREFIX, name)
+    };
+    let wip = list_wip_refs(project_dir)?
+        .into_iter()
+        .find(|r| r.name == full)
+        .with_context(|| format!("no WIP ref named {}", full))?;
+    let worktree = project_dir.join(".worktrees").join(&wip.agent_name);
+    if worktree.join(".git").exists() {
+        git_stdout(&worktree, &["cherry-pick", "--no-commit", &wip.commit])
+            .context("could not apply WIP snapshot; commit or stash the worktree first")?;
+        git_stdout(&worktree, &["reset", "--quiet"])?;
+        return Ok(WipRestore::Applied(worktree));
+    }
+    let branch = format!("wip/{}-{}", wip.agent_name, wip.saved_at);
+    git_stdout(project_dir, &["branch", &branch, &wip.commit])?;
+    Ok(WipRestore::Branch(branch))
+}
+
+/// Delete a task branch once its work has landed on the target branch.
+/// The branch's worktree must be removed first.
+pub fn delete_branch(project_dir: &std::path::Path, branch: &str) -> Result<()> {
+    let status = Command::new("git")
+        .args(["branch", "-D", branch])
+        .current_dir(project_dir)
+        .status()
+        .context("failed to run git branch -D")?;
+    if !status.success() {
+        anyhow::bail!("git branch -D {} failed with status {}", branch, status);
+    }
+    Ok(())
+}
+
+/// Result of merging a task branch without the LLM merger.
+pub enum NativeMerge {
+    /// The target branch advanced (fast-forward or clean merge commit).
+    Merged,
+    /// The merge conflicted and was aborted.
+    Conflicts,
+    /// The project checkout can't host the merge (other branch checked out,
+    /// dirty tree, target moved on).
+    Unavailable(String),
+}
+
+/// Merge `branch` into `target_branch` in the project checkout, preferring a
+/// fast-forward. Conflicting merges are aborted, leaving the checkout untouched.
+pub fn try_native_merge(
+    project_dir: &std::path::Path,
+    branch: &str,
+    target_branch: &str,
+) -> Result<NativeMerge> {
+    if let Some(reason) = checkout_unavailable(project_dir, target_branch)? {
+        return Ok(NativeMerge::Unavailable(reason));
+    }
+    if git_succeeds(project_dir, &["merge", "--ff-only", "--quiet", branch]) {
+        tracing::info!("Fast-forwarded {} to {}", target_branch, branch);
+        return Ok(NativeMerge::Merged);
+    }
+    if git_succeeds(project_dir, &["merge", "--no-edit", "--quiet", branch]) {
+        tracing::info!("Merged {} into {} without conflicts", branch, target_branch);
+        return Ok(NativeMerge::Merged);
+    }
+    let _ = Command::new("git")
+        .args(["merge", "--abort"])
+        .current_dir(project_dir)
+        .status();
+    Ok(NativeMerge::Conflicts)
+}
+
+/// Why the project checkout can't take a merge into `target_branch`, if it can't.
+fn checkout_unavailable(
+    project_dir: &std::path::Path,
+    target_branch: &str,
+) -> Result<Option<String>> {
+    let current = git_stdout(project_dir, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+    if current != target_branch {
+        return Ok(Some(format!(
+            "project checkout is on {current}, not {target_branch}"
+        )));
+    }
+    let dirty = git_stdout(
+        project_dir,
+        &["status", "--porcelain", "--untracked-files=no"],
+    )?;
+    if !dirty.is_empty() {
+        return Ok(Some("project checkout has uncommitted changes".to_string()));
+    }
+    Ok(None)
+}
+
+/// Result of merging a task branch in a scratch worktree.
+pub enum ScratchMerge {
+    /// The merge commit (or fast-forwarded branch tip), checked out detached.
+    Merged { commit: String },
+    /// The merge conflicted; the scratch worktree was removed.
+    Conflicts,
+}
+
+/// Merge `branch` into `target_branch` in a detached scratch worktree at
+/// `path`, leaving the project checkout and the target branch alone. Shared
+/// dependency dirs and setup files are brought in as for task worktrees.
+pub fn merge_in_scratch_worktree(
+    project_dir: &std::path::Path,
+    path: &std::path::Path,
+    branch: &str,
+    target_branch: &str,
+    setup: &WorktreeSetup,
+) -> Result<ScratchMerge> {
+    remove_scratch_worktree(project_dir, path);
+    let path_str = path.to_str().context("worktree path is not valid UTF-8")?;
+    git_stdout(
+        project_dir,
+        &[
+            "worktree",
+            "add",
+            "--force",
+            "--detach",
+            path_str,
+            target_branch,
+        ],
+    )?;
+    let merged = git_succeeds(path, &["merge", "--ff-only", "--quiet", branch])
+        || git_succeeds(path, &["merge", "--no-edit", "--quiet", branch]);
+    if !merged {
+        remove_scratch_worktree(project_dir, path);
+        return Ok(ScratchMerge::Conflicts);
+    }
+    link_shared_dependency_dirs(project_dir, path, &setup.link);
+    copy_setup_files(project_dir, path, &setup.copy);
+    let commit = git_stdout(path, &["rev-parse", "HEAD"])?;
+    Ok(ScratchMerge::Merged { commit })
+}
+
+/// Remove a scratch worktree. It holds no work of its own, so nothing is saved.
+pub fn remove_scratch_worktree(project_dir: &std::path::Path, path: &std::path::Path) {
+    if path.exists() {
+        let _ = Command::new("git")
+            .arg("worktree")
+            .arg("remove")
+            .arg("--force")
+            .arg(path)
+            .current_dir(project_dir)
+            .status();
+    }
+    prune_stale_worktrees(project_dir);
+}
+
+/// Fast-forward the checked-out `target_branch` to `commit`, a merge prepared
+/// in a scratch worktree. Unavailable when the target moved on meanwhile.
+pub fn fast_forward_target(
+    project_dir: &std::path::Path,
+    target_branch: &str,
+    commit: &str,
+) -> Result<NativeMerge> {
+    if let Some(reason) = checkout_unavailable(project_dir, target_branch)? {
+        return Ok(NativeMerge::Unavailable(reason));
+    }
+    if git_succeeds(project_dir, &["merge", "--ff-only", "--quiet", commit]) {
+        tracing::info!(
+            "Fast-forwarded {} to checked merge {}",
+            target_branch,
+            commit
+        );
+        return Ok(NativeMerge::Merged);
+    }
+    Ok(NativeMerge::Unavailable(format!(
+        "{target_branch} moved on while the merge was checked"
+    )))
+}
+
+fn git_stdout(dir: &std::path::Path, args: &[&str]) -> Result<String> {
+    run_git_stdout(Command::new("git").current_dir(dir), args)
+}
+
+fn git_stdout_with_index(
+    dir: &std::path::Path,
+    index: &std::path::Path,
+    args: &[&str],
+) -> Result<String> {
+    run_git_stdout(
+        Command::new("git")
+            .current_dir(dir)
+            .env("GIT_INDEX_FILE", index),
+        args,
+    )
+}
+
+fn run_git_stdout(command: &mut Command, args: &[&str]) -> Result<String> {
+    let output = command
+        .args(args)
+        .output()
+        .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn git_succeeds(dir: &std::path::Path, args: &[&str]) -> bool {
+    Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
@@ -4,10 +4,12 @@ use std::sync::atomic::Ordering;
 use std::time::Duration;
 
 use agent_bus::Bus;
-use agent_orchestrator::agent::{Agent, permission_mode_for_role, role_has_tools};
+use agent_orchestrator::agent::{Agent, AgentExit, permission_mode_for_role, role_has_tools};
 use agent_orchestrator::bus_tools::bus_tools_for_role;
+use agent_orchestrator::dispatch::{record_answer, resumes_after_answer};
+use agent_orchestrator::runtime::OrchestratorRuntime;
 use agent_orchestrator::types::AgentRole;
-use support::{FakeCompleter, test_config, test_runtime};
+use support::{FakeCompleter, RejectingReviewer, test_config, test_runtime};
 
 // ---------------------------------------------------------------------------
 // Agent-level tests
@@ -53,6 +55,95 @@ async fn agent_processes_bus_messages() {
     assert_eq!(call_count.load(Ordering::SeqCst), 2);
 }
 
+#[tokio::test]
+async fn agent_commits_work_and_exits_on_shutdown_request() {
+    let bus = Bus::new();
+    let config = test_config(AgentRole::TaskAgent, 0, None);
+    let bus_name = config.agent_id.bus_name();
+    let agent_mailbox = bus.register(&bus_name).unwrap();
+    let sender = bus.register("runtime").unwrap();
+
+    let fake = FakeCompleter::with_texts(vec!["working", "committed"]);
+    let call_count = fake.call_count.clone();
+    let handle = tokio::spawn(Agent::with_completer(config, agent_mailbox, Box::new(fake)).run());
+
+    sender
+        .send(
+            &bus_name,
+            "task_assignment",
+            serde_json::json!({"content": "do the task"}),
+        )
+        .unwrap();
+    sender
+        .send(&bus_name, "shutdown_requested", serde_json::json!({}))
+        .unwrap();
+
+    let result = tokio::time::timeout(Duration::from_secs(2), handle).await;
+    let exit = result.expect("agent should exit after shutdown_requested");
+    assert_eq!(
+        exit.unwrap().unwrap(),
+        AgentExit::Shutdown { committed: true }
+    );
+    assert_eq!(call_count.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn shutdown_request_interrupts_a_running_turn() {
+    let bus = Bus::new();
+    let config = test_config(AgentRole::TaskAgent, 0, None);
+    let bus_name = config.agent_id.bus_name();
+    let agent_mailbox = bus.register(&bus_name).unwrap();
+    let sender = bus.register("runtime").unwrap();
+
+    let fake = FakeCompleter::with_texts(vec!["never finishes", "committed"])
+        .with_first_delay(Duration::from_secs(60));
+    let call_count = fake.call_count.clone();
+    let handle = tokio::spawn(Agent::with_completer(config, agent_mailbox, Box::new(fake)).run());
+
+    sender
+        .send(
+            &bus_name,
+            "task_assignment",
+            serde_json::json!({"content": "do the task"}),
+        )
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    sender
+        .send(&bus_name, "shutdown_requested", serde_json::json!({}))
+        .unwrap();
+
+    let result = tokio::time::timeout(Duration::from_secs(2), handle).await;
+    let exit = result.expect("shutdown should not wait for the running turn");
+    assert_eq!(
+        exit.unwrap().unwrap(),
+        AgentExit::Shutdown { committed: true }
+    );
+    assert_eq!(call_count.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn shutdown_without_a_task_reports_nothing_committed() {
+    let bus = Bus::new();
+    let config = test_config(AgentRole::TaskAgent, 0, None);
+    let bus_name = config.agent_id.bus_name();
+    let agent_mailbox = bus.register(&bus_name).unwrap();
+    let sender = bus.register("runtime").unwrap();
+
+    let fake = FakeCompleter::with_texts(vec![]);
+    let handle = tokio::spawn(Agent::with_completer(config, agent_mailbox, Box::new(fake)).run());
+    sender
+        .send(&bus_name, "shutdown_requested", serde_json::json!({}))
+        .unwrap();
+
+    let exit = tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .expect("agent should exit after shutdown_requested");
+    assert_eq!(
+        exit.unwrap().unwrap(),
+        AgentExit::Shutdown { committed: false }
+    );
+}
+
 // ---------------------------------------------------------------------------
 // Tool restriction tests
 // ---------------------------------------------------------------------------
@@ -176,10 +267,36 @@ async fn runtime_startup_validates_pending_tasks() {
     );
 }
 
+#[tokio::test]
+async fn paused_startup_validates_pending_tasks_on_resume() {
+    let bus = Bus::new();
+    let (mut rt, _) = test_runtime(bus, vec!["ok"]).await.unwrap();
+
+    let db = rt.db();
+    let task = db
+        .create_task("test task", Some("needs validation"), 1, "test")
+        .await
+        .unwrap();
+    let project = rt.project().to_string();
+    rt.global_limits.set_paused(&project, true);
+
+    rt.bootstrap_pending_tasks().await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let t = db.get_task(&task.id).await.unwrap();
+    assert_eq!(t.status, "pending", "paused startup defers validation");
+
+    rt.global_limits.set_paused(&project, false);
+    rt.handle_message("dispatch_resumed", &serde_json::json!({}), "control")
+        .await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let t = db.get_task(&task.id).await.unwrap();
+    assert_ne!(t.status, "pending", "resume runs the deferred validation");
+}
+
 #[tokio::test]
 async fn startup_validation_does_not_override_in_progress_task() {
     let bus = Bus::new();
-    let (rt, _) = test_runtime(bus, vec!["ok"]).await.unwrap();
+    let (mut rt, _) = test_runtime(bus, vec!["ok"]).await.unwrap();
 
     let db = rt.db();
     let task = db
@@ -202,6 +319,64 @@ async fn startup_validation_does_not_override_in_progress_task() {
     assert_eq!(t.assignee.as_deref(), Some("task-test"));
 }
 
+#[tokio::test]
+async fn merge_failed_moves_task_to_merge_conflict() {
+    let bus = Bus::new();
+    let (mut rt, _) = test_runtime(bus, vec!["ok"]).await.unwrap();
+
+    let db = rt.db();
+    let task = db
+        .create_task("test task", Some("conflicting change"), 1, "test")
+        .await
+        .unwrap();
+    db.close_task(&task.id, "reviewer").await.unwrap();
+
+    let content = format!("Conflict in src/lib.rs merging agent/task-{}", task.id);
+    let payload = serde_json::json!({"content": content, "from_agent": "merger"});
+    rt.handle_message("merge_failed", &payload, "merger").await;
+
+    let t = db.get_task(&task.id).await.unwrap();
+    assert_eq!(t.status, "merge_conflict");
+    let comments = db.get_comments(&task.id).await.unwrap();
+    assert!(
+        !comments.is_empty(),
+        "merge failure should be recorded as a comment"
+    );
+}
+
+#[tokio::test]
+async fn merge_report_for_another_branch_leaves_queued_merge_pending() {
+    let bus = Bus::new();
+    let (mut rt, _) = test_runtime(bus, vec!["ok"]).await.unwrap();
+
+    let db = rt.db();
+    let queued = db
+        .create_task("queued", Some("waiting for the merger"), 1, "test")
+        .await
+        .unwrap();
+    let reported = db
+        .create_task("reported", Some("merged before a restart"), 1, "test")
+        .await
+        .unwrap();
+    db.close_task(&queued.id, "reviewer").await.unwrap();
+    db.close_task(&reported.id, "reviewer").await.unwrap();
+    rt.insert_pending_merge(&queued.id);
+
+    let content = format!("Conflict merging agent/task-{}", reported.id);
+    let payload = serde_json::json!({"content": content, "from_agent": "merger"});
+    rt.handle_message("merge_failed", &payload, "merger").await;
+
+    let t = db.get_task(&reported.id).await.unwrap();
+    assert_eq!(t.status, "merge_conflict");
+    let t = db.get_task(&queued.id).await.unwrap();
+    assert_eq!(t.status, "done", "the queued merge must not be settled");
+
+    let payload = serde_json::json!({"content": "Conflict", "from_agent": "merger"});
+    rt.handle_message("merge_failed", &payload, "merger").await;
+    let t = db.get_task(&queued.id).await.unwrap();
+    assert_eq!(t.status, "merge_conflict");
+}
+
 #[tokio::test]
 async fn handle_message_ignores_unknown_kind() {
     let bus = Bus::new();
@@ -211,3 +386,252 @@ async fn handle_message_ignores_unknown_kind() {
     let should_exit = rt.handle_message("unknown_kind", &payload, "someone").await;
     assert!(!should_exit);
 }
+
+#[tokio::test]
+async fn status_request_replies_with_task_counts() {
+    let bus = Bus::new();
+    let (mut rt, _) = test_runtime(bus.clone(), vec!["ok"]).await.unwrap();
+    let mut probe = bus.register("probe").unwrap();
+
+    let db = rt.db();
+    db.create_task("pending task", Some("not validated yet"), 1, "test")
+        .await
+        .unwrap();
+
+    let payload = serde_json::json!({});
+    rt.handle_message("status_request", &payload, "probe").await;
+
+    let reply = tokio::time::timeout(Duration::from_secs(1), probe.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(reply.kind, "status_reply");
+    let status: agent_orchestrator::control::RuntimeStatus =
+        serde_json::from_value(reply.payload).unwrap();
+    assert_eq!(status.task_counts.get("pending"), Some(&1));
+    assert!(status.agents.is_empty());
+}
+
+#[tokio::test]
+async fn cancel_task_marks_task_cancelled_or_requeues() {
+    let bus = Bus::new();
+    let (mut rt, _) = test_runtime(bus.clone(), vec!["ok"]).await.unwrap();
+    let mut probe = bus.register("probe").unwrap();
+
+    let db = rt.db();
+    let cancelled = db
+        .create_task("runaway task", Some("spins forever"), 1, "test")
+        .await
+        .unwrap();
+    let requeued = db
+        .create_task("stuck task", Some("try again"), 1, "test")
+        .await
+        .unwrap();
+    for task in [&cancelled, &requeued] {
+        let updates = llm_tasks::db::TaskUpdates {
+            status: Some("in_progress"),
+            assignee: Some("task-gone"),
+            ..Default::default()
+        };
+        db.update_task(&task.id, updates, "test").await.unwrap();
+    }
+    rt.insert_pending_merge(&cancelled.id);
+
+    let payload = serde_json::json!({"task_id": cancelled.id, "requeue": false});
+    rt.handle_message("cancel_task", &payload, "probe").await;
+    let reply = cancel_reply(&mut probe).await;
+    assert_eq!(reply["error"], serde_json::Value::Null);
+    let payload = serde_json::json!({"task_id": requeued.id, "requeue": true});
+    rt.handle_message("cancel_task", &payload, "probe").await;
+    let reply = cancel_reply(&mut probe).await;
+    assert_eq!(reply["error"], serde_json::Value::Null);
+
+    let t = db.get_task(&cancelled.id).await.unwrap();
+    assert_eq!(t.status, "cancelled");
+    assert_eq!(t.assignee, None);
+    let t = db.get_task(&requeued.id).await.unwrap();
+    assert_eq!(t.status, "ready");
+    assert_eq!(t.assignee, None);
+
+    // The cancelled task's queued merge is gone, so a bare merge report
+    // has nothing to settle.
+    let payload = serde_json::json!({"content": "Conflict", "from_agent": "merger"});
+    rt.handle_message("merge_failed", &payload, "merger").await;
+    let t = db.get_task(&cancelled.id).await.unwrap();
+    assert_eq!(t.status, "cancelled");
+
+    let payload = serde_json::json!({"task_id": cancelled.id, "requeue": true});
+    rt.handle_message("cancel_task", &payload, "probe").await;
+    let reply = cancel_reply(&mut probe).await;
+    assert!(
+        reply["error"]
+            .as_str()
+            .unwrap()
+            .contains("already cancelled")
+    );
+    let payload = serde_json::json!({"task_id": "no-such-task", "requeue": false});
+    rt.handle_message("cancel_task", &payload, "probe").await;
+    let reply = cancel_reply(&mut probe).await;
+    assert!(reply["error"].is_string());
+    let t = db.get_task(&cancelled.id).await.unwrap();
+    assert_eq!(t.status, "cancelled");
+}
+
+async fn cancel_reply(probe: &mut agent_bus::Mailbox) -> serde_json::Value {
+    let reply = tokio::time::timeout(Duration::from_secs(1), probe.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(reply.kind, "cancel_reply");
+    reply.payload
+}
+
+#[tokio::test]
+async fn review_rejection_without_live_agent_requeues_task() {
+    let bus = Bus::new();
+    let (mut rt, _) = test_runtime(bus, vec!["ok"]).await.unwrap();
+
+    let db = rt.db();
+    let task = db
+        .create_task("reviewed task", Some("missing tests"), 1, "test")
+        .await
+        .unwrap();
+    let updates = llm_tasks::db::TaskUpdates {
+        status: Some("in_review"),
+        assignee: Some("task-gone"),
+        ..Default::default()
+    };
+    db.update_task(&task.id, updates, "test").await.unwrap();
+
+    let payload = serde_json::json!({"task_id": task.id, "content": "No tests added"});
+    rt.handle_message("review_rejected", &payload, "arch-reviewed")
+        .await;
+
+    let events = db.get_events(&task.id).await.unwrap();
+    assert!(
+        events.iter().any(|e| e.field.as_deref() == Some("status")
+            && e.new_value.as_deref() == Some("ready")
+            && e.actor == "reviewer"),
+        "rejection with no agent to revise should requeue the task"
+    );
+}
+
+/// Hand the next message addressed to the runtime to `rt`; returns its kind.
+async fn pump(rt: &mut OrchestratorRuntime, mailbox: &mut agent_bus::Mailbox) -> String {
+    let msg = tokio::time::timeout(Duration::from_secs(5), mailbox.recv())
+        .await
+        .expect("runtime message")
+        .expect("runtime mailbox open");
+    rt.handle_message(&msg.kind, &msg.payload, &msg.from).await;
+    msg.kind
+}
+
+/// Create a ready task, dispatch it to a live fake agent, and return its id.
+async fn dispatch_reviewed_task(rt: &mut OrchestratorRuntime) -> String {
+    let db = rt.db();
+    let task = db
+        .create_task("revised task", Some("add a parser"), 1, "test")
+        .await
+        .unwrap();
+    let updates = llm_tasks::db::TaskUpdates {
+        status: Some("ready"),
+        ..Default::default()
+    };
+    db.update_task(&task.id, updates, "test").await.unwrap();
+    rt.run_watchdog_and_dispatch().await;
+    task.id
+}
+
+async fn claim_count(rt: &OrchestratorRuntime, task_id: &str) -> usize {
+    let events = rt.db().get_events(task_id).await.unwrap();
+    events.iter().filter(|e| e.action == "claimed").count()
+}
+
+#[tokio::test]
+async fn review_rejection_sends_feedback_to_the_live_agent() {
+    let bus = Bus::new();
+    let mut runtime_mailbox = bus.register("runtime").unwrap();
+    let (mut rt, calls) = test_runtime(bus, vec!["done", "revised"]).await.unwrap();
+    rt.set_reviewer(std::sync::Arc::new(RejectingReviewer::default()));
+
+    let task_id = dispatch_reviewed_task(&mut rt).await;
+    while pump(&mut rt, &mut runtime_mailbox).await != "review_rejected" {}
+
+    let task = rt.db().get_task(&task_id).await.unwrap();
+    assert_eq!(task.status, "in_progress", "revision continues the task");
+    assert_eq!(claim_count(&rt, &task_id).await, 1, "same attempt");
+
+    while pump(&mut rt, &mut runtime_mailbox).await != "task_complete" {}
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        2,
+        "the held agent worked on the review feedback"
+    );
+    let task = rt.db().get_task(&task_id).await.unwrap();
+    assert_eq!(task.status, "in_review");
+    assert_eq!(claim_count(&rt, &task_id).await, 1);
+}
+
+#[tokio::test]
+async fn exhausted_revisions_requeue_the_task() {
+    let bus = Bus::new();
+    let mut runtime_mailbox = bus.register("runtime").unwrap();
+    let (mut rt, calls) = test_runtime(bus, vec!["done", "revised", "revised again"])
+        .await
+        .unwrap();
+    let reviewer = RejectingReviewer::default();
+    let reviews = reviewer.reviews.clone();
+    rt.set_reviewer(std::sync::Arc::new(reviewer));
+
+    let task_id = dispatch_reviewed_task(&mut rt).await;
+    // Two revisions (the default max_revisions), then the third rejection requeues.
+    let mut rejections = 0;
+    while rejections < 3 {
+        if pump(&mut rt, &mut runtime_mailbox).await == "review_rejected" {
+            rejections += 1;
+        }
+    }
+
+    assert_eq!(reviews.load(Ordering::SeqCst), 3);
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        3,
+        "initial work and two revisions"
+    );
+    let task = rt.db().get_task(&task_id).await.unwrap();
+    assert_eq!(task.status, "ready", "requeued for a fresh attempt");
+    assert_eq!(task.assignee, None);
+    assert_eq!(claim_count(&rt, &task_id).await, 1);
+}
+
+#[tokio::test]
+async fn answering_needs_info_task_requeues_it_with_the_answer() {
+    let bus = Bus::new();
+    let (rt, _) = test_runtime(bus, vec!["ok"]).await.unwrap();
+
+    let db = rt.db();
+    let task = db
+        .create_task("blocked task", Some("which API?"), 1, "test")
+        .await
+        .unwrap();
+    let err = record_answer(&db, &task.id, "Use v2", "user").await;
+    assert!(err.is_err(), "only needs_info tasks take answers");
+
+    let updates = llm_tasks::db::TaskUpdates {
+        status: Some("needs_info"),
+        assignee: Some("task-gone"),
+        ..Default::default()
+    };
+    db.update_task(&task.id, updates, "test").await.unwrap();
+    record_answer(&db, &task.id, "Use v2", "user")
+        .await
+        .unwrap();
+
+    let t = db.get_task(&task.id).await.unwrap();
+    assert_eq!(t.status, "ready");
+    assert_eq!(t.assignee, None);
+    let comments = db.get_comments(&task.id).await.unwrap();
+    assert!(comments.iter().any(|c| c.content == "Answer: Use v2"));
+    let events = db.get_events(&task.id).await.unwrap();
+    assert!(resumes_after_answer(&events));
+}
@@ -1,9 +1,13 @@
 use std::collections::VecDeque;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use agent_bus::Bus;
 use agent_orchestrator::agent::{Agent, AgentConfig, BackendKind, Completer};
+use agent_orchestrator::reviewer::{
+    CompletedWork, ReviewResult, Reviewer, TaskProposal, ValidateResult,
+};
 use agent_orchestrator::runtime::{AgentFactory, OrchestratorRuntime};
 use agent_orchestrator::types::{AgentId, AgentRole};
 use anyhow::Result;
@@ -18,6 +22,7 @@ pub use test_scenario::{AuditFinding, PerformanceMetrics, TestOutcome, TestScena
 pub struct FakeCompleter {
     responses: Arc<Mutex<VecDeque<Result<llm_sdk::Output, llm_sdk::Error>>>>,
     pub call_count: Arc<AtomicUsize>,
+    first_delay: Option<Duration>,
 }
 
 impl FakeCompleter {
@@ -25,9 +30,16 @@ impl FakeCompleter {
         Self {
             responses: Arc::new(Mutex::new(VecDeque::from(responses))),
             call_count: Arc::new(AtomicUsize::new(0)),
+            first_delay: None,
         }
     }
 
+    /// Make the first completion take `delay`, like a long-running turn.
+    pub fn with_first_delay(mut self, delay: Duration) -> Self {
+        self.first_delay = Some(delay);
+        self
+    }
+
     pub fn with_texts(texts: Vec<&str>) -> Self {
         let responses = texts.into_iter().map(|t| Ok(fake_output(t))).collect();
         Self::new(responses)
@@ -53,12 +65,36 @@ impl FakeCompleter {
 #[async_trait]
 impl Completer for FakeCompleter {
     async fn complete(&mut self, _prompt: &str) -> Result<llm_sdk::Output, llm_sdk::Error> {
-        self.call_count.fetch_add(1, Ordering::SeqCst);
+        if self.call_count.fetch_add(1, Ordering::SeqCst) == 0
+            && let Some(delay) = self.first_delay
+        {
+            tokio::time::sleep(delay).await;
+        }
         let mut q = self.responses.lock().unwrap();
         q.pop_front().unwrap_or_else(|| Ok(fake_output("")))
     }
 }
 
+/// Reviewer that approves every task and rejects every completion.
+#[derive(Default)]
+pub struct RejectingReviewer {
+    pub reviews: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Reviewer for RejectingReviewer {
+    async fn validate(&self, _task: &TaskProposal<'_>) -> Result<ValidateResult, String> {
+        Ok(ValidateResult::Approved("clear".to_string()))
+    }
+
+    async fn review(&self, _work: &CompletedWork<'_>) -> Result<ReviewResult, String> {
+        let n = self.reviews.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(ReviewResult::Incomplete(format!(
+            "Missing tests (review {n})"
+        )))
+    }
+}
+
 pub fn fake_output(text: &str) -> llm_sdk::Output {
     llm_sdk::Output {
         text: text.to_string(),
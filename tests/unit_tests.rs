@@ -3,13 +3,15 @@
 mod support;
 
 use agent_bus::Bus;
-use agent_orchestrator::agent::{permission_mode_for_role, role_has_tools};
+use agent_orchestrator::agent::{is_task_message, permission_mode_for_role, role_has_tools};
 use agent_orchestrator::bus_tools::bus_tools_for_role;
-use agent_orchestrator::config;
-use agent_orchestrator::runtime_support::resolve_sandbox;
+use agent_orchestrator::config::{self, WorktreeSetup};
+use agent_orchestrator::runtime_support::{SandboxExtras, resolve_sandbox, resolve_sandbox_with};
 use agent_orchestrator::types::{AgentId, AgentRole};
 use agent_orchestrator::worktree::{
-    link_project_root_alias, link_shared_dependency_dirs, link_worktree_aliases,
+    WipRestore, WorktreeConfig, create_worktree, link_project_root_alias,
+    link_shared_dependency_dirs, link_worktree_aliases, list_wip_refs, remove_worktree,
+    restore_wip,
 };
 use std::time::Duration;
 use support::{TestAgentBuilder, TestBench, assert_agent_registered, test_config};
@@ -96,6 +98,16 @@ fn tool_permissions_are_role_specific() {
     assert!(role_has_tools(AgentRole::Merger));
 }
 
+#[test]
+fn task_messages_include_resume() {
+    assert!(is_task_message("task_assignment"));
+    assert!(is_task_message("task_resume"));
+    assert!(is_task_message("check_failed"));
+    assert!(is_task_message("review_feedback"));
+    assert!(!is_task_message("external_message"));
+    assert!(!is_task_message("shutdown_requested"));
+}
+
 #[test]
 fn permission_modes_are_consistent() {
     for role in [AgentRole::TaskAgent, AgentRole::Merger] {
@@ -371,7 +383,7 @@ fn resumed_worktree_links_shared_vendor_dir() {
     std::fs::create_dir_all(&vendor_bin).unwrap();
     std::fs::write(vendor_bin.join("phpstan"), "#!/bin/sh\n").unwrap();
 
-    link_shared_dependency_dirs(&project, &worktree);
+    link_shared_dependency_dirs(&project, &worktree, &WorktreeSetup::default().link);
     let linked = worktree.join("vendor");
     let meta = std::fs::symlink_metadata(&linked).unwrap();
 
@@ -408,7 +420,7 @@ fn worktree_root_alias_points_back_to_project_root() {
 
 #[cfg(unix)]
 #[test]
-fn gc_phpstan_fixes_worktree_alias_points_to_current_worktree() {
+fn worktree_aliases_point_to_current_worktree() {
     let root = std::env::temp_dir().join(format!(
         "orch-worktree-gc-alias-test-{}",
         uuid::Uuid::new_v4()
@@ -418,9 +430,10 @@ fn gc_phpstan_fixes_worktree_alias_points_to_current_worktree() {
     let second = project.join(".worktrees").join("task-second");
     std::fs::create_dir_all(&first).unwrap();
     std::fs::create_dir_all(&second).unwrap();
+    let aliases = vec!["gc".to_string()];
 
-    link_worktree_aliases(&project, &first);
-    link_worktree_aliases(&project, &second);
+    link_worktree_aliases(&project, &first, &aliases);
+    link_worktree_aliases(&project, &second, &aliases);
 
     let alias = project.join(".worktrees").join("gc");
     let meta = std::fs::symlink_metadata(&alias).unwrap();
@@ -434,7 +447,7 @@ fn gc_phpstan_fixes_worktree_alias_points_to_current_worktree() {
 }
 
 #[test]
-fn task_agent_sandbox_binds_gc_alias_to_current_worktree() {
+fn task_agent_sandbox_expands_worktree_placeholder_in_mounts() {
     let root = std::env::temp_dir().join(format!(
         "orch-sandbox-gc-alias-test-{}",
         uuid::Uuid::new_v4()
@@ -442,9 +455,18 @@ fn task_agent_sandbox_binds_gc_alias_to_current_worktree() {
     let project = root.join("gc-phpstan-fixes");
     let worktree = project.join(".worktrees").join("task-lt-abc");
     std::fs::create_dir_all(&worktree).unwrap();
-    link_worktree_aliases(&project, &worktree);
+    let extras = SandboxExtras {
+        mounts: vec![("{worktree}".to_string(), "/tmp/gc".to_string())],
+        ..Default::default()
+    };
 
-    let (_, prefix) = resolve_sandbox(AgentRole::TaskAgent, &project, Ok(worktree.clone()), true);
+    let (_, prefix) = resolve_sandbox_with(
+        AgentRole::TaskAgent,
+        &project,
+        Ok(worktree.clone()),
+        true,
+        &extras,
+    );
 
     let has_gc_mount = prefix
         .windows(3)
@@ -457,3 +479,124 @@ fn task_agent_sandbox_binds_gc_alias_to_current_worktree() {
 
     let _ = std::fs::remove_dir_all(&root);
 }
+
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// A git repo with one empty commit on `main`.
+fn init_git_project(root: &std::path::Path) -> std::path::PathBuf {
+    let project = root.join("project");
+    std::fs::create_dir_all(&project).unwrap();
+    git(&project, &["init", "-q", "-b", "main"]);
+    git(&project, &["config", "user.email", "test@example.com"]);
+    git(&project, &["config", "user.name", "Test"]);
+    git(&project, &["commit", "-q", "--allow-empty", "-m", "init"]);
+    project
+}
+
+#[test]
+fn fresh_worktree_gets_copied_files_and_defers_post_create() {
+    let root = std::env::temp_dir().join(format!("orch-setup-test-{}", uuid::Uuid::new_v4()));
+    let project = init_git_project(&root);
+    std::fs::write(project.join(".env"), "APP_ENV=test\n").unwrap();
+    let setup = WorktreeSetup {
+        copy: vec![".env".to_string(), "missing.txt".to_string()],
+        post_create: Some("echo done > post_create.out".to_string()),
+        ..Default::default()
+    };
+    let cfg = WorktreeConfig {
+        project_dir: project.clone(),
+        agent_name: "task-lt-setup".to_string(),
+        target_branch: "main".to_string(),
+    };
+
+    let prepared = create_worktree(&cfg, &setup).unwrap();
+    let worktree = prepared.path;
+
+    assert!(prepared.fresh);
+    assert_eq!(
+        std::fs::read_to_string(worktree.join(".env")).unwrap(),
+        "APP_ENV=test\n"
+    );
+    assert!(!worktree.join("missing.txt").exists());
+    assert!(
+        !worktree.join("post_create.out").exists(),
+        "post_create runs in the agent's sandbox, not on worktree creation"
+    );
+    assert!(!create_worktree(&cfg, &setup).unwrap().fresh);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn linked_dirs_and_copied_files_are_not_saved_as_wip() {
+    let root = std::env::temp_dir().join(format!("orch-wip-link-test-{}", uuid::Uuid::new_v4()));
+    let project = init_git_project(&root);
+    std::fs::create_dir_all(project.join("deps")).unwrap();
+    std::fs::write(project.join("local.env"), "KEY=1\n").unwrap();
+    let setup = WorktreeSetup {
+        link: vec!["deps".to_string()],
+        copy: vec!["local.env".to_string()],
+        ..Default::default()
+    };
+    let cfg = WorktreeConfig {
+        project_dir: project.clone(),
+        agent_name: "task-lt-linked".to_string(),
+        target_branch: "main".to_string(),
+    };
+
+    let worktree = create_worktree(&cfg, &setup).unwrap().path;
+    assert!(worktree.join("deps").is_symlink());
+    remove_worktree(&cfg, &setup).unwrap();
+
+    assert!(list_wip_refs(&project).unwrap().is_empty());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn removing_dirty_worktree_saves_wip_ref_that_can_be_restored() {
+    let root = std::env::temp_dir().join(format!("orch-wip-test-{}", uuid::Uuid::new_v4()));
+    let project = init_git_project(&root);
+    let setup = WorktreeSetup::default();
+
+    let cfg = WorktreeConfig {
+        project_dir: project.clone(),
+        agent_name: "task-lt-wip".to_string(),
+        target_branch: "main".to_string(),
+    };
+    let worktree = create_worktree(&cfg, &setup).unwrap().path;
+    std::fs::write(worktree.join("notes.txt"), "half done\n").unwrap();
+    remove_worktree(&cfg, &setup).unwrap();
+    assert!(!worktree.exists(), "worktree should be removed");
+
+    let refs = list_wip_refs(&project).unwrap();
+    assert_eq!(refs.len(), 1, "expected one WIP ref, got {:?}", refs);
+    assert_eq!(refs[0].agent_name, "task-lt-wip");
+
+    let restored = restore_wip(&project, &refs[0].name).unwrap();
+    let WipRestore::Branch(branch) = restored else {
+        panic!("expected a branch without a worktree, got {:?}", restored);
+    };
+    git(
+        &project,
+        &["cat-file", "-e", &format!("{branch}:notes.txt")],
+    );
+
+    let worktree = create_worktree(&cfg, &setup).unwrap().path;
+    assert!(!worktree.join("notes.txt").exists());
+    let restored = restore_wip(&project, &refs[0].name).unwrap();
+    assert_eq!(restored, WipRestore::Applied(worktree.clone()));
+    assert_eq!(
+        std::fs::read_to_string(worktree.join("notes.txt")).unwrap(),
+        "half done\n"
+    );
+
+    let _ = std::fs::remove_dir_all(&root);
+}